@@ -1,40 +1,73 @@
 extends Control
 
 signal game_start
+signal pause_toggled
+signal restart
+signal continue_requested
 
 # Declare member variables here. Examples:
 # var a = 2
 # var b = "text"
-var start_flag = false
+var state = "Title"
 
 # Called when the node enters the scene tree for the first time.
 func _ready():
 	$GameOverGroup.visible = false
+	$PauseLabel.visible = false
+	$ContinueLabel.visible = false
 
 
 # Called every frame. 'delta' is the elapsed time since the previous frame.
 func _process(delta):
-	if !start_flag:
+	if state == "Title":
 		start_game_watcher()
+	if state == "Continue" and Input.is_action_just_pressed("shoot"):
+		emit_signal("continue_requested")
+	if Input.is_action_just_pressed("pause"):
+		emit_signal("pause_toggled")
 
 func start_game_watcher():
 	if Input.is_action_pressed("shoot"):
-		start_flag = true
-		$TitleGroup.visible = false
 		emit_signal("game_start")
 
-func _on_stage_set_remain(remain):
-	$Panel/RemainLabel.text = str(remain - 1 if remain > 1 else 0)
+func _on_stage_state_changed(new_state):
+	state = new_state
+	# the title stays over the demo
+	$TitleGroup.visible = state == "Title" or state == "Demo"
+	$GameOverGroup.visible = state == "GameOver"
+	$PauseLabel.visible = state == "Paused"
+	$ContinueLabel.visible = state == "Continue"
 
+func _on_stage_continue_offered(seconds_left, credits):
+	$ContinueLabel.text = "CONTINUE? " + str(seconds_left) + "\nCREDIT " + str(credits)
 
-func _on_stage_set_score(score):
-	$Panel/SocreValLabel.text = str(score)
+var p2_remain = 0
+var p2_score = 0
+
+func _on_stage_set_remain(remain, player = 0):
+	if player == 0:
+		$Panel/RemainLabel.text = str(remain - 1 if remain > 1 else 0)
+	else:
+		p2_remain = remain - 1 if remain > 1 else 0
+		update_p2_label()
+
+
+func _on_stage_set_score(score, player = 0):
+	if player == 0:
+		$Panel/SocreValLabel.text = str(score)
+	else:
+		p2_score = score
+		update_p2_label()
+
+func update_p2_label():
+	$Panel/P2Label.visible = true
+	$Panel/P2Label.text = "2P  SCORE : " + str(p2_score) + "  x " + str(p2_remain)
 
 
 func _on_ResetButton_pressed():
-	get_tree().reload_current_scene()
+	emit_signal("restart")
 
 
-func _on_stage_game_over():
-	$GameOverGroup.visible = true
+func _on_stage_game_over(cause = ""):
 	$GameOverGroup/ScoreLabel.text = "SCORE : " + $Panel/SocreValLabel.text
+	$GameOverGroup/CauseLabel.text = cause
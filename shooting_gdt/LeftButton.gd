@@ -14,7 +14,7 @@ func _ready():
 
 # Called every frame. 'delta' is the elapsed time since the previous frame.
 func _process(delta):
-	var now_flag = Input.is_action_pressed("ui_left")
+	var now_flag = Input.is_action_pressed("move_left")
 	if now_flag != flag:
 		if now_flag:
 			set_texture(pressed)
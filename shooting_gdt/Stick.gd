@@ -13,9 +13,8 @@ func _input(event):
 		# $"../".stick_vec = Vector2(0.0, 0.0);
 		return
 
-	var threshold = abs(p.x) > 50 or abs(p.y) > 50;
-	var leftright_vec = p.x / 500.0 if threshold else 0;
-	var updown_vec = p.y / 500.0 if threshold else 0;
-	var new_vec = Vector2(leftright_vec, updown_vec);
+	var new_vec = p / 200.0;
+	if new_vec.length() > 1.0:
+		new_vec = new_vec.normalized();
 	# print(new_vec)
 	$"../".stick_vec = new_vec;
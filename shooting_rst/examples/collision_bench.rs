@@ -0,0 +1,68 @@
+//! Headless benchmark of the Rust-side collision pass.
+//!
+//! cargo run --release --example collision_bench -- [bullets] [targets] [ticks]
+
+use shooting_rst::collision::{self, Circle, SpatialHash};
+use std::env;
+use std::time::Instant;
+
+// small xorshift, enough to scatter circles over the field
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+fn arg(n: usize, default: usize) -> usize {
+    env::args()
+        .nth(n)
+        .and_then(|a| a.parse().ok())
+        .unwrap_or(default)
+}
+
+fn main() {
+    let bullet_num = arg(1, 1000);
+    let target_num = arg(2, 100);
+    let ticks = arg(3, 600);
+
+    let mut rng = Rng(2463534242);
+    let mut bullets: Vec<Circle> = (0..bullet_num)
+        .map(|_| Circle::new(rng.range(-12.0, 12.0), rng.range(-27.0, 10.0), 0.25))
+        .collect();
+    let targets: Vec<Circle> = (0..target_num)
+        .map(|_| Circle::new(rng.range(-12.0, 12.0), rng.range(-27.0, 10.0), 0.8))
+        .collect();
+
+    let mut grid = SpatialHash::new(2.0);
+    let mut hit_num = 0;
+    let start = Instant::now();
+    for _ in 0..ticks {
+        for b in bullets.iter_mut() {
+            b.z += 0.1;
+            if b.z > 10.0 {
+                b.z = -27.0;
+            }
+        }
+        hit_num += collision::collide(&mut grid, &bullets, &targets).len();
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{} bullets x {} targets, {} ticks: {:?} total, {:?} per tick, {} hits",
+        bullet_num,
+        target_num,
+        ticks,
+        elapsed,
+        elapsed / ticks.max(1) as u32,
+        hit_num
+    );
+}
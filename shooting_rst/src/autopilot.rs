@@ -0,0 +1,262 @@
+//! A pilot that can stand in for the stick: dodges predicted bullets and lines up under aliens.
+//!
+//! Pure, the Godot Player and the headless sim both feed it positions and velocities on the xz plane.
+
+use std::f32::consts::FRAC_1_SQRT_2;
+
+/// A bullet or alien, assumed to keep its velocity over the horizon.
+#[derive(Clone, Copy, Debug)]
+pub struct Threat {
+    pub x: f32,
+    pub z: f32,
+    pub vx: f32,
+    pub vz: f32,
+    pub r: f32,
+}
+
+impl Threat {
+    pub fn new(x: f32, z: f32, vx: f32, vz: f32, r: f32) -> Self {
+        Threat { x, z, vx, vz, r }
+    }
+
+    fn at(&self, t: f32) -> (f32, f32) {
+        (self.x + self.vx * t, self.z + self.vz * t)
+    }
+
+    fn speed(&self) -> f32 {
+        (self.vx * self.vx + self.vz * self.vz).sqrt()
+    }
+}
+
+/// Where the ship may go, as in `Env` (up is the smaller z).
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    pub left: f32,
+    pub right: f32,
+    pub up: f32,
+    pub down: f32,
+}
+
+impl Limits {
+    fn clamp(&self, x: f32, z: f32) -> (f32, f32) {
+        (
+            x.max(self.left).min(self.right),
+            z.max(self.up).min(self.down),
+        )
+    }
+}
+
+/// The ship being flown. `speed` and `focus_speed` are the speeds the caller will
+/// actually move at for a full stick, with and without focus, while not shooting.
+#[derive(Clone, Copy, Debug)]
+pub struct Ship {
+    pub x: f32,
+    pub z: f32,
+    pub r: f32,
+    pub speed: f32,
+    pub focus_speed: f32,
+    /// speed ratio while shooting
+    pub shoot_weight: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Decision {
+    /// stick, length 0 or 1
+    pub x: f32,
+    pub z: f32,
+    pub shoot: bool,
+    pub focus: bool,
+}
+
+const DIRS: [(f32, f32); 9] = [
+    (0.0, 0.0),
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+    (FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    (-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+];
+
+const HIT_PENALTY: f32 = 1000.0;
+// how far up from the bottom limit the ship likes to sit, as a fraction of the height
+const REST_HEIGHT: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Autopilot {
+    /// seconds looked ahead
+    pub horizon: f32,
+    /// samples over the horizon
+    pub steps: u32,
+    /// clearance wanted beyond the hit radii
+    pub margin: f32,
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Autopilot {
+            horizon: 0.6,
+            steps: 8,
+            margin: 0.6,
+        }
+    }
+}
+
+impl Autopilot {
+    /// Tries each stick direction, with and without focus, and keeps the safest one.
+    /// Ties go to the move that ends closest under the nearest alien ahead.
+    pub fn decide(
+        &self,
+        ship: &Ship,
+        limits: &Limits,
+        threats: &[Threat],
+        targets: &[(f32, f32)],
+    ) -> Decision {
+        let reach = ship.speed.max(ship.focus_speed) * self.horizon + ship.r + self.margin;
+        let near: Vec<Threat> = threats
+            .iter()
+            .filter(|t| {
+                let d = ((t.x - ship.x).powi(2) + (t.z - ship.z).powi(2)).sqrt();
+                d - t.r - t.speed() * self.horizon <= reach
+            })
+            .copied()
+            .collect();
+        let aim = targets
+            .iter()
+            .filter(|&&(_, z)| z < ship.z)
+            .min_by(|a, b| {
+                let da = (a.0 - ship.x).abs() + (ship.z - a.1) * 0.25;
+                let db = (b.0 - ship.x).abs() + (ship.z - b.1) * 0.25;
+                da.total_cmp(&db)
+            })
+            .map(|&(x, _)| x);
+        let rest_z = limits.down - (limits.down - limits.up) * REST_HEIGHT;
+        // the ship moves slower while it shoots
+        let shoot = !targets.is_empty();
+        let weight = if shoot { ship.shoot_weight } else { 1.0 };
+
+        let mut best = Decision::default();
+        let mut best_score = f32::MIN;
+        for &focus in [false, true].iter() {
+            let speed = weight * if focus { ship.focus_speed } else { ship.speed };
+            for &(dx, dz) in DIRS.iter() {
+                let (end_x, end_z, danger) =
+                    self.danger(ship, limits, &near, dx * speed, dz * speed);
+                let mut score = -danger;
+                if let Some(x) = aim {
+                    score -= (end_x - x).abs() * 0.5;
+                }
+                score -= (end_z - rest_z).abs() * 0.1;
+                if focus {
+                    score -= 0.05;
+                }
+                if score > best_score {
+                    best_score = score;
+                    best = Decision {
+                        x: dx,
+                        z: dz,
+                        shoot,
+                        focus,
+                    };
+                }
+            }
+        }
+        best
+    }
+
+    // Walks the ship along (vx, vz) and sums how far each sample eats into the margin.
+    // Earlier samples weigh more, the prediction gets worse further out.
+    fn danger(
+        &self,
+        ship: &Ship,
+        limits: &Limits,
+        threats: &[Threat],
+        vx: f32,
+        vz: f32,
+    ) -> (f32, f32, f32) {
+        let steps = self.steps.max(1);
+        let mut pos = (ship.x, ship.z);
+        let mut danger = 0.0;
+        for k in 1..=steps {
+            let t = self.horizon * k as f32 / steps as f32;
+            pos = limits.clamp(ship.x + vx * t, ship.z + vz * t);
+            let weight = (steps - k + 1) as f32;
+            for threat in threats {
+                let (tx, tz) = threat.at(t);
+                let clearance =
+                    ((pos.0 - tx).powi(2) + (pos.1 - tz).powi(2)).sqrt() - threat.r - ship.r;
+                if clearance < 0.0 {
+                    danger += HIT_PENALTY * weight;
+                } else if clearance < self.margin {
+                    danger += (self.margin - clearance) * weight;
+                }
+            }
+        }
+        (pos.0, pos.1, danger)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: Limits = Limits {
+        left: -10.0,
+        right: 10.0,
+        up: -20.0,
+        down: 1.0,
+    };
+
+    // at the resting height, so only threats and targets move it
+    fn ship(shoot_weight: f32) -> Ship {
+        Ship {
+            x: 0.0,
+            z: LIMITS.down - (LIMITS.down - LIMITS.up) * REST_HEIGHT,
+            r: 0.9,
+            speed: 10.0,
+            focus_speed: 5.0,
+            shoot_weight,
+        }
+    }
+
+    #[test]
+    fn rests_and_holds_fire_without_targets() {
+        let d = Autopilot::default().decide(&ship(0.5), &LIMITS, &[], &[]);
+        assert_eq!(d, Decision::default());
+    }
+
+    #[test]
+    fn lines_up_under_an_alien_ahead() {
+        let d = Autopilot::default().decide(&ship(0.5), &LIMITS, &[], &[(5.0, -15.0)]);
+        assert!(d.shoot);
+        assert!(d.x > 0.0);
+    }
+
+    #[test]
+    fn steps_aside_from_a_bullet() {
+        let s = ship(0.5);
+        let bullet = Threat::new(0.0, s.z - 3.0, 0.0, 10.0, 0.25);
+        let d = Autopilot::default().decide(&s, &LIMITS, &[bullet], &[]);
+        assert!(d.x != 0.0);
+    }
+
+    #[test]
+    fn plans_at_the_shooting_speed() {
+        // 3 ahead is reached in the horizon at 5, which is the focus speed or half speed
+        let pilot = Autopilot::default();
+        let target = [(3.0, -15.0)];
+        let slowed = pilot.decide(&ship(0.5), &LIMITS, &[], &target);
+        assert_eq!((slowed.x, slowed.focus), (1.0, false));
+        let full = pilot.decide(&ship(1.0), &LIMITS, &[], &target);
+        assert_eq!((full.x, full.focus), (1.0, true));
+    }
+
+    #[test]
+    fn survives_nan_targets() {
+        let targets = [(f32::NAN, -15.0), (2.0, f32::NAN), (1.0, -15.0)];
+        let d = Autopilot::default().decide(&ship(0.5), &LIMITS, &[], &targets);
+        assert!(d.shoot);
+    }
+}
@@ -0,0 +1,68 @@
+//! Barrage shapes shared by the game and the headless tools.
+
+use std::f32::consts::PI;
+
+/// Number of barrage kinds an alien picks from.
+pub const BARRAGE_KINDS: usize = 7;
+
+/// Speed of alien bullets relative to the alien firing them.
+pub const BULLET_SPEED_RATIO: f32 = 1.3;
+
+/// Angles on the xz plane, relative to the aimed direction, of the bullets of barrage `kind`.
+pub fn barrage_angles(kind: usize) -> Vec<f32> {
+    match kind % BARRAGE_KINDS {
+        // simple barrage
+        0..=3 => vec![0.0],
+        // three way barrage
+        4 | 5 => vec![0.0, PI / 3.0, -PI / 3.0],
+        // all range barrage
+        _ => (0..6).map(|i| PI * i as f32 / 3.0).collect(),
+    }
+}
+
+/// What players call barrage `kind`.
+pub fn barrage_name(kind: usize) -> &'static str {
+    match kind % BARRAGE_KINDS {
+        0..=3 => "Simple",
+        4 | 5 => "Three-Way",
+        _ => "All-Range",
+    }
+}
+
+pub fn rotate_xz(x: f32, z: f32, theta: f32) -> (f32, f32) {
+    let c = theta.cos();
+    let s = theta.sin();
+    (x * c - z * s, x * s + z * c)
+}
+
+/// A bullet on the xz plane. Moves in a straight line a frame at a time like `AlienBullet`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shot {
+    pub x: f32,
+    pub z: f32,
+    pub vx: f32,
+    pub vz: f32,
+}
+
+impl Shot {
+    pub fn advance(&mut self, dt: f32) {
+        self.x += self.vx * dt;
+        self.z += self.vz * dt;
+    }
+}
+
+/// The bullets fired from (`x`, `z`) towards the unit vector (`dx`, `dz`), one per angle.
+pub fn volley(x: f32, z: f32, dx: f32, dz: f32, speed: f32, angles: &[f32]) -> Vec<Shot> {
+    angles
+        .iter()
+        .map(|&theta| {
+            let (vx, vz) = rotate_xz(dx, dz, theta);
+            Shot {
+                x,
+                z,
+                vx: vx * speed,
+                vz: vz * speed,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,260 @@
+//! Draws the bullet paths of a barrage to an SVG, for reviewing patterns without Godot.
+//!
+//! cargo run --no-default-features --features sim --bin barrage_svg -- \
+//!     [--level level.json] [--kind 0] [--seconds 3] [--fps 60] [--heat 1] [--speed S] \
+//!     [--from 0,-20] [--aim 0,1] [--volleys 1] [--interval S] [--slices 0.5,1,2] [--out out.svg]
+
+use shooting_rst::barrage::{volley, Shot, BULLET_SPEED_RATIO};
+use shooting_rst::sim::{Level, Rect};
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::process;
+
+struct Args {
+    level: Option<String>,
+    kind: usize,
+    seconds: f32,
+    fps: u32,
+    heat: f32,
+    speed: Option<f32>,
+    from: (f32, f32),
+    aim: (f32, f32),
+    volleys: u32,
+    interval: Option<f32>,
+    slices: Vec<f32>,
+    out: Option<String>,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: barrage_svg [--level FILE] [--kind N] [--seconds S] [--fps N] [--heat H] \
+         [--speed S] [--from X,Z] [--aim X,Z] [--volleys N] [--interval S] [--slices T,T,..] \
+         [--out FILE]"
+    );
+    process::exit(2);
+}
+
+fn parse_list(value: &str) -> Vec<f32> {
+    value
+        .split(',')
+        .map(|v| v.trim().parse().unwrap_or_else(|_| usage()))
+        .collect()
+}
+
+fn parse_pair(value: &str) -> (f32, f32) {
+    match parse_list(value).as_slice() {
+        &[x, z] => (x, z),
+        _ => usage(),
+    }
+}
+
+// intervals are divided by the heat
+fn parse_heat(value: &str) -> f32 {
+    match value.parse::<f32>() {
+        Ok(heat) if heat.is_finite() && heat > 0.0 => heat,
+        _ => {
+            eprintln!("--heat must be greater than 0");
+            usage()
+        }
+    }
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        level: None,
+        kind: 0,
+        seconds: 3.0,
+        fps: 60,
+        heat: 1.0,
+        speed: None,
+        from: (0.0, -20.0),
+        aim: (0.0, 1.0),
+        volleys: 1,
+        interval: None,
+        slices: Vec::new(),
+        out: None,
+    };
+    let mut it = env::args().skip(1);
+    while let Some(flag) = it.next() {
+        let value = it.next().unwrap_or_else(|| usage());
+        match flag.as_str() {
+            "--level" => args.level = Some(value),
+            "--kind" => args.kind = value.parse().unwrap_or_else(|_| usage()),
+            "--seconds" => args.seconds = value.parse().unwrap_or_else(|_| usage()),
+            "--fps" => args.fps = value.parse().unwrap_or_else(|_| usage()),
+            "--heat" => args.heat = parse_heat(&value),
+            "--speed" => args.speed = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--from" => args.from = parse_pair(&value),
+            "--aim" => args.aim = parse_pair(&value),
+            "--volleys" => args.volleys = value.parse().unwrap_or_else(|_| usage()),
+            "--interval" => args.interval = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--slices" => args.slices = parse_list(&value),
+            "--out" => args.out = Some(value),
+            _ => usage(),
+        }
+    }
+    args
+}
+
+/// One bullet from the frame it was fired until it left the field.
+struct Trail {
+    points: Vec<(f32, f32)>,
+    // positions at each slice, None when not yet fired or already gone
+    at_slices: Vec<Option<(f32, f32)>>,
+}
+
+fn simulate(level: &Level, args: &Args) -> Vec<Trail> {
+    let dt = 1.0 / args.fps.max(1) as f32;
+    let frames = (args.seconds * args.fps as f32).ceil() as u32;
+    let speed = args.speed.unwrap_or(level.alien_speed * BULLET_SPEED_RATIO) * args.heat;
+    // aliens fire every alien_fire_min seconds at the least, faster with heat
+    let interval = args.interval.unwrap_or(level.alien_fire_min / args.heat);
+    let (ax, az) = args.aim;
+    let len = (ax * ax + az * az).sqrt().max(f32::EPSILON);
+    let angles = level.barrage(args.kind);
+
+    let mut trails: Vec<Trail> = Vec::new();
+    let mut flying: Vec<(usize, Shot)> = Vec::new();
+    let mut fired = 0;
+    for frame in 0..=frames {
+        let t = frame as f32 * dt;
+        if fired < args.volleys && t >= fired as f32 * interval {
+            for shot in volley(args.from.0, args.from.1, ax / len, az / len, speed, &angles) {
+                flying.push((trails.len(), shot));
+                trails.push(Trail {
+                    points: vec![(shot.x, shot.z)],
+                    at_slices: vec![None; args.slices.len()],
+                });
+            }
+            fired += 1;
+        }
+        for (i, &slice) in args.slices.iter().enumerate() {
+            if (t - slice).abs() < dt / 2.0 {
+                for &(trail, shot) in flying.iter() {
+                    trails[trail].at_slices[i] = Some((shot.x, shot.z));
+                }
+            }
+        }
+        for (trail, shot) in flying.iter_mut() {
+            shot.advance(dt);
+            trails[*trail].points.push((shot.x, shot.z));
+        }
+        // bullets outside the alien limits are despawned like in the game
+        flying.retain(|(_, shot)| level.outer.contains(shot.x, shot.z));
+    }
+    trails
+}
+
+// slice colors, cycled
+const SLICE_COLORS: [&str; 6] = [
+    "#ffd166", "#ef476f", "#06d6a0", "#118ab2", "#f78c6b", "#c77dff",
+];
+
+fn render(level: &Level, args: &Args, trails: &[Trail]) -> String {
+    let Rect {
+        left,
+        right,
+        up,
+        down,
+    } = level.outer;
+    let inner = level.inner;
+    let r = level.alien_bullet_radius;
+    let mut svg = String::new();
+    // svg y runs down the screen like z
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        left,
+        up,
+        right - left,
+        down - up,
+        (right - left) * 20.0,
+        (down - up) * 20.0
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="black"/>"#,
+        left,
+        up,
+        right - left,
+        down - up
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="#555" stroke-width="0.1" stroke-dasharray="0.5"/>"##,
+        inner.left,
+        inner.up,
+        inner.right - inner.left,
+        inner.down - inner.up
+    );
+    for trail in trails {
+        let points: Vec<String> = trail
+            .points
+            .iter()
+            .map(|(x, z)| format!("{:.3},{:.3}", x, z))
+            .collect();
+        let _ = writeln!(
+            svg,
+            r##"<polyline points="{}" fill="none" stroke="#0bccff" stroke-opacity="0.6" stroke-width="0.08"/>"##,
+            points.join(" ")
+        );
+    }
+    for (i, slice) in args.slices.iter().enumerate() {
+        let color = SLICE_COLORS[i % SLICE_COLORS.len()];
+        for (x, z) in trails.iter().filter_map(|t| t.at_slices[i]) {
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{:.3}" cy="{:.3}" r="{}" fill="{}"/>"#,
+                x, z, r, color
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-size="1" fill="{}">t={}s</text>"#,
+            left + 0.5,
+            up + 1.5 + i as f32 * 1.2,
+            color,
+            slice
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r#"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="red" stroke-width="0.1"/>"#,
+        args.from.0, args.from.1, level.alien_radius
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" font-size="1" fill="white">kind {} heat {} {}s</text>"#,
+        left + 0.5,
+        down - 0.5,
+        args.kind,
+        args.heat,
+        args.seconds
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn main() {
+    let args = parse_args();
+    let level = match args.level.as_ref() {
+        Some(path) => Level::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        None => Level::default(),
+    };
+
+    let trails = simulate(&level, &args);
+    let svg = render(&level, &args, &trails);
+    match args.out.as_ref() {
+        Some(path) => {
+            if let Err(e) = fs::write(path, svg) {
+                eprintln!("failed to write {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", svg),
+    }
+}
@@ -0,0 +1,126 @@
+//! Receives telemetry batches on localhost and appends each run summary to a JSON Lines file.
+//!
+//! cargo run --no-default-features --features sim --bin collector -- \
+//!     [--addr 127.0.0.1:8787] [--out telemetry.jsonl] [--fail N]
+//!
+//! Point the Stage's `telemetry_endpoint` at `http://127.0.0.1:8787/runs`.
+//! `--fail N` answers 503 to the first N batches, to try the offline queue.
+
+use shooting_rst::http::{error_body, read_request, respond};
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::TcpListener;
+use std::process;
+
+struct Args {
+    addr: String,
+    out: String,
+    fail: u32,
+}
+
+fn usage() -> ! {
+    eprintln!("usage: collector [--addr HOST:PORT] [--out FILE] [--fail N]");
+    process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        addr: "127.0.0.1:8787".to_string(),
+        out: "telemetry.jsonl".to_string(),
+        fail: 0,
+    };
+    let mut it = env::args().skip(1);
+    while let Some(flag) = it.next() {
+        let value = it.next().unwrap_or_else(|| usage());
+        match flag.as_str() {
+            "--addr" => args.addr = value,
+            "--out" => args.out = value,
+            "--fail" => args.fail = value.parse().unwrap_or_else(|_| usage()),
+            _ => usage(),
+        }
+    }
+    args
+}
+
+// the summaries of one batch, each as a line
+fn runs(body: &str) -> Result<Vec<String>, String> {
+    let batch: serde_json::Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let runs = batch
+        .get("runs")
+        .and_then(|r| r.as_array())
+        .ok_or("expected {\"runs\": [...]}")?;
+    runs.iter()
+        .map(|run| {
+            if run.is_object() {
+                Ok(run.to_string())
+            } else {
+                Err("runs must be objects".to_string())
+            }
+        })
+        .collect()
+}
+
+fn main() {
+    let args = parse_args();
+    let listener = TcpListener::bind(&args.addr).unwrap_or_else(|e| {
+        eprintln!("failed to listen on {}: {}", args.addr, e);
+        process::exit(1);
+    });
+    eprintln!("collecting on {} into {}", args.addr, args.out);
+    let mut fail_left = args.fail;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("accept failed: {}", e);
+                continue;
+            }
+        };
+        let request = match read_request(&stream) {
+            Ok(request) => request,
+            Err(e) => {
+                respond(&stream, 400, &error_body(&e));
+                continue;
+            }
+        };
+        // CORS preflight of the wasm build, `respond` adds the allowed methods and headers
+        if request.method == "OPTIONS" {
+            respond(&stream, 204, "");
+            continue;
+        }
+        if request.method != "POST" {
+            respond(&stream, 405, &error_body("POST batches here"));
+            continue;
+        }
+        if fail_left > 0 {
+            fail_left -= 1;
+            eprintln!("refusing a batch, {} more to refuse", fail_left);
+            respond(&stream, 503, &error_body("try later"));
+            continue;
+        }
+        let lines = match runs(&request.body) {
+            Ok(lines) => lines,
+            Err(e) => {
+                eprintln!("rejected a batch: {}", e);
+                respond(&stream, 400, &error_body(&e));
+                continue;
+            }
+        };
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&args.out)
+            .and_then(|mut file| lines.iter().try_for_each(|l| writeln!(file, "{}", l)));
+        match written {
+            Ok(()) => {
+                eprintln!("stored {} runs", lines.len());
+                respond(&stream, 204, "");
+            }
+            Err(e) => {
+                eprintln!("failed to write {}: {}", args.out, e);
+                respond(&stream, 500, &error_body(&e.to_string()));
+            }
+        }
+    }
+}
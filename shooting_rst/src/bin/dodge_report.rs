@@ -0,0 +1,158 @@
+//! Reports bullet density and whether each volley of a barrage can be dodged at a stage heat.
+//! Exits with 1 when a pattern is flagged as undodgeable.
+//!
+//! cargo run --no-default-features --features sim --bin dodge_report -- \
+//!     [--level level.json] [--kind all|N] [--wave wave.json] [--heat 1] [--fps 60] \
+//!     [--from 0,-20] [--aim 0,1] [--volleys 3] [--interval S] [--detail none|steps|maps]
+
+use shooting_rst::sim::{barrage_wave, Analysis, Emit, Level, PatternReport};
+use std::env;
+use std::fs;
+use std::process;
+
+struct Args {
+    level: Option<String>,
+    kind: Option<usize>,
+    wave: Option<String>,
+    heat: f32,
+    fps: u32,
+    from: (f32, f32),
+    aim: (f32, f32),
+    volleys: u32,
+    interval: Option<f32>,
+    detail: String,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: dodge_report [--level FILE] [--kind all|N] [--wave FILE] [--heat H] [--fps N] \
+         [--from X,Z] [--aim X,Z] [--volleys N] [--interval S] [--detail none|steps|maps]"
+    );
+    process::exit(2);
+}
+
+fn parse_pair(value: &str) -> (f32, f32) {
+    let v: Vec<f32> = value
+        .split(',')
+        .map(|v| v.trim().parse().unwrap_or_else(|_| usage()))
+        .collect();
+    match v.as_slice() {
+        &[x, z] => (x, z),
+        _ => usage(),
+    }
+}
+
+// intervals are divided by the heat
+fn parse_heat(value: &str) -> f32 {
+    match value.parse::<f32>() {
+        Ok(heat) if heat.is_finite() && heat > 0.0 => heat,
+        _ => {
+            eprintln!("--heat must be greater than 0");
+            usage()
+        }
+    }
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        level: None,
+        kind: None,
+        wave: None,
+        heat: 1.0,
+        fps: 60,
+        from: (0.0, -20.0),
+        aim: (0.0, 1.0),
+        volleys: 3,
+        interval: None,
+        detail: "none".to_string(),
+    };
+    let mut it = env::args().skip(1);
+    while let Some(flag) = it.next() {
+        let value = it.next().unwrap_or_else(|| usage());
+        match flag.as_str() {
+            "--level" => args.level = Some(value),
+            "--kind" if value == "all" => args.kind = None,
+            "--kind" => args.kind = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--wave" => args.wave = Some(value),
+            "--heat" => args.heat = parse_heat(&value),
+            "--fps" => args.fps = value.parse().unwrap_or_else(|_| usage()),
+            "--from" => args.from = parse_pair(&value),
+            "--aim" => args.aim = parse_pair(&value),
+            "--volleys" => args.volleys = value.parse().unwrap_or_else(|_| usage()),
+            "--interval" => args.interval = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--detail" => args.detail = value,
+            _ => usage(),
+        }
+    }
+    args
+}
+
+fn load_wave(path: &str) -> Vec<Emit> {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("failed to read {}: {}", path, e);
+        process::exit(1);
+    });
+    serde_json::from_str(&text).unwrap_or_else(|e| {
+        eprintln!("failed to parse {}: {}", path, e);
+        process::exit(1);
+    })
+}
+
+fn main() {
+    let args = parse_args();
+    let level = match args.level.as_ref() {
+        Some(path) => Level::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        None => Level::default(),
+    };
+    let analysis = Analysis {
+        heat: args.heat,
+        fps: args.fps,
+        keep_steps: args.detail != "none",
+        keep_maps: args.detail == "maps",
+        ..Analysis::default()
+    };
+
+    let reports: Vec<PatternReport> = match args.wave.as_ref() {
+        Some(path) => vec![analysis.run(&level, path, &load_wave(path))],
+        None => {
+            // aliens fire every alien_fire_min seconds at the least, faster with heat
+            let interval = args.interval.unwrap_or(level.alien_fire_min / args.heat);
+            let kinds: Vec<usize> = match args.kind {
+                Some(kind) => vec![kind],
+                None => (0..level.barrage_kinds()).collect(),
+            };
+            kinds
+                .into_iter()
+                .map(|kind| {
+                    let wave = barrage_wave(kind, args.from, args.aim, args.volleys, interval);
+                    analysis.run(&level, &format!("kind {}", kind), &wave)
+                })
+                .collect()
+        }
+    };
+
+    let flagged: Vec<&str> = reports
+        .iter()
+        .filter(|r| r.undodgeable)
+        .map(|r| r.name.as_str())
+        .collect();
+    let out = serde_json::json!({
+        "heat": args.heat,
+        "flagged": flagged,
+        "patterns": reports,
+    });
+    match serde_json::to_string_pretty(&out) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("failed to write report: {}", e);
+            process::exit(1);
+        }
+    }
+    if !flagged.is_empty() {
+        eprintln!("undodgeable at heat {}: {}", args.heat, flagged.join(", "));
+        process::exit(1);
+    }
+}
@@ -0,0 +1,249 @@
+//! An in-memory leaderboard on localhost, to try submitting and fetching without a service.
+//!
+//! cargo run --no-default-features --features sim --bin leaderboard_mock -- \
+//!     [--addr 127.0.0.1:8788] [--fail N]
+//!
+//! Point the Stage's `leaderboard_url` at `http://127.0.0.1:8788`.
+//! `--fail N` answers 503 to the first N requests, to try the error path.
+//! The board is ranked by score, ties going to the earlier submission, and forgotten on exit.
+//! Each submission is re-run on the sim core with the default level first: a replay that
+//! does not come to its claim is refused, and one recorded by the Godot game, which the core
+//! cannot re-run, is held off the board.
+
+use shooting_rst::http::{error_body, read_request, respond, Request};
+use shooting_rst::leaderboard::{
+    standings_json, Receipt, Standing, Submission, MAX_LIMIT, SUBMIT_PATH,
+};
+use shooting_rst::replay::Replay;
+use shooting_rst::sim::{verify, Claim, Level};
+use std::env;
+use std::net::TcpListener;
+use std::process;
+
+const DEFAULT_LIMIT: u32 = 10;
+const DEFAULT_SPAN: u32 = 5;
+
+struct Args {
+    addr: String,
+    fail: u32,
+}
+
+fn usage() -> ! {
+    eprintln!("usage: leaderboard_mock [--addr HOST:PORT] [--fail N]");
+    process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        addr: "127.0.0.1:8788".to_string(),
+        fail: 0,
+    };
+    let mut it = env::args().skip(1);
+    while let Some(flag) = it.next() {
+        let value = it.next().unwrap_or_else(|| usage());
+        match flag.as_str() {
+            "--addr" => args.addr = value,
+            "--fail" => args.fail = value.parse().unwrap_or_else(|_| usage()),
+            _ => usage(),
+        }
+    }
+    args
+}
+
+struct Entry {
+    id: u64,
+    submission: Submission,
+}
+
+/// Entries in rank order, and the ones held unranked.
+#[derive(Default)]
+struct Board {
+    entries: Vec<Entry>,
+    held: Vec<Entry>,
+    next_id: u64,
+}
+
+impl Board {
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Ranks the submission below the entries with the same score.
+    fn insert(&mut self, submission: Submission) -> Receipt {
+        let id = self.next_id();
+        let at = self
+            .entries
+            .iter()
+            .position(|e| e.submission.score < submission.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(at, Entry { id, submission });
+        Receipt {
+            id,
+            rank: Some(at as u32 + 1),
+        }
+    }
+
+    fn hold(&mut self, submission: Submission) -> Receipt {
+        let id = self.next_id();
+        self.held.push(Entry { id, submission });
+        Receipt { id, rank: None }
+    }
+
+    fn is_held(&self, id: u64) -> bool {
+        self.held.iter().any(|e| e.id == id)
+    }
+
+    fn standings(&self, from: usize, to: usize) -> Vec<Standing> {
+        self.entries[from..to.min(self.entries.len())]
+            .iter()
+            .enumerate()
+            .map(|(i, e)| Standing {
+                rank: (from + i) as u32 + 1,
+                id: e.id,
+                name: e.submission.name.clone(),
+                score: e.submission.score,
+                seed: e.submission.seed,
+                replay_hash: e.submission.replay_hash,
+            })
+            .collect()
+    }
+
+    fn position(&self, id: u64) -> Option<usize> {
+        self.entries.iter().position(|e| e.id == id)
+    }
+}
+
+fn number_query(request: &Request, key: &str, default: u32) -> Result<u32, String> {
+    match request.query(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("{} is not a number", key)),
+        None => Ok(default),
+    }
+}
+
+// ranks the submission only when its replay comes to what it claims
+fn submit(board: &mut Board, submission: Submission) -> (u16, String) {
+    // from_json has parsed the replay already
+    let replay = match Replay::parse(&submission.replay) {
+        Ok(replay) => replay,
+        Err(e) => return (400, error_body(&e.to_string())),
+    };
+    let claim = Claim {
+        score: submission.score,
+        kills: submission.kills,
+        seconds: submission.seconds,
+    };
+    let verdict = verify(Level::default(), &replay, claim);
+    if !verdict.verified {
+        eprintln!(
+            "{} scored {} under the {} rules, held unranked",
+            submission.name,
+            submission.score,
+            replay.rules.name()
+        );
+        return (202, board.hold(submission).to_json());
+    }
+    if !verdict.accepted {
+        return (422, error_body(&verdict.mismatches.join(", ")));
+    }
+    eprintln!(
+        "{} scored {} with seed {}",
+        submission.name, submission.score, submission.seed
+    );
+    (201, board.insert(submission).to_json())
+}
+
+// status and body of the answer
+fn handle(board: &mut Board, request: &Request) -> (u16, String) {
+    let path = request.path.trim_end_matches('/');
+    let method = request.method.as_str();
+    // CORS preflight of the wasm build, `respond` adds the allowed methods and headers
+    if method == "OPTIONS" {
+        return (204, String::new());
+    }
+    if path == SUBMIT_PATH {
+        if method != "POST" {
+            return (405, error_body("POST submissions here"));
+        }
+        return match Submission::from_json(&request.body) {
+            Ok(submission) => submit(board, submission),
+            Err(e) => (400, error_body(&e.message)),
+        };
+    }
+    if path == "/scores/top" {
+        if method != "GET" {
+            return (405, error_body("GET the top standings"));
+        }
+        return match number_query(request, "limit", DEFAULT_LIMIT) {
+            Ok(limit) => {
+                let limit = limit.clamp(1, MAX_LIMIT) as usize;
+                (200, standings_json(&board.standings(0, limit)))
+            }
+            Err(e) => (400, error_body(&e)),
+        };
+    }
+    if let Some(id) = path.strip_prefix("/scores/around/") {
+        if method != "GET" {
+            return (405, error_body("GET the standings around an entry"));
+        }
+        let id: u64 = match id.parse() {
+            Ok(id) => id,
+            Err(_) => return (400, error_body("the entry id is not a number")),
+        };
+        let span = match number_query(request, "span", DEFAULT_SPAN) {
+            Ok(span) => span.min(MAX_LIMIT / 2) as usize,
+            Err(e) => return (400, error_body(&e)),
+        };
+        return match board.position(id) {
+            Some(at) => (
+                200,
+                standings_json(&board.standings(at.saturating_sub(span), at + span + 1)),
+            ),
+            None if board.is_held(id) => (404, error_body("the entry is held unranked")),
+            None => (404, error_body("no such entry")),
+        };
+    }
+    (404, error_body("no such path"))
+}
+
+fn main() {
+    let args = parse_args();
+    let listener = TcpListener::bind(&args.addr).unwrap_or_else(|e| {
+        eprintln!("failed to listen on {}: {}", args.addr, e);
+        process::exit(1);
+    });
+    eprintln!("leaderboard on {}", args.addr);
+    let mut board = Board::default();
+    let mut fail_left = args.fail;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("accept failed: {}", e);
+                continue;
+            }
+        };
+        let request = match read_request(&stream) {
+            Ok(request) => request,
+            Err(e) => {
+                respond(&stream, 400, &error_body(&e));
+                continue;
+            }
+        };
+        // preflights pass, so the request they clear is the one refused
+        if fail_left > 0 && request.method != "OPTIONS" {
+            fail_left -= 1;
+            eprintln!("refusing a request, {} more to refuse", fail_left);
+            respond(&stream, 503, &error_body("try later"));
+            continue;
+        }
+        let (status, body) = handle(&mut board, &request);
+        if status >= 400 {
+            eprintln!("{} {}: {} {}", request.method, request.path, status, body);
+        }
+        respond(&stream, status, &body);
+    }
+}
@@ -0,0 +1,118 @@
+//! Runs the game rules without Godot and prints a JSON summary.
+//!
+//! cargo run --no-default-features --features sim --bin sim -- \
+//!     [--level level.json] [--seed 1] [--seconds 120] [--fps 60] [--policy random|autopilot|script.json] \
+//!     [--record replay.txt]
+//!
+//! `--record` saves the inputs of the run as a replay, for verify_replay.
+
+use shooting_rst::replay::{Replay, ReplayInput};
+use shooting_rst::sim::{
+    AutopilotPolicy, Input, InputPolicy, Level, RandomPolicy, ScriptedPolicy, World,
+};
+use std::env;
+use std::fs;
+use std::process;
+
+struct Args {
+    level: Option<String>,
+    seed: u64,
+    seconds: f32,
+    fps: u32,
+    policy: String,
+    record: Option<String>,
+}
+
+// passes the inputs of a policy through and keeps them, clamped the way a replay holds them
+struct Recorder<'a> {
+    policy: &'a mut dyn InputPolicy,
+    replay: Replay,
+}
+
+impl InputPolicy for Recorder<'_> {
+    fn input(&mut self, world: &World) -> Input {
+        let input = ReplayInput::from(self.policy.input(world));
+        if let Err(e) = self.replay.push(input) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        Input::from(input)
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: sim [--level FILE] [--seed N] [--seconds S] [--fps N] [--policy random|autopilot|FILE] \
+         [--record FILE]"
+    );
+    process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        level: None,
+        seed: 1,
+        seconds: 120.0,
+        fps: 60,
+        policy: "random".to_string(),
+        record: None,
+    };
+    let mut it = env::args().skip(1);
+    while let Some(flag) = it.next() {
+        let value = it.next().unwrap_or_else(|| usage());
+        match flag.as_str() {
+            "--level" => args.level = Some(value),
+            "--seed" => args.seed = value.parse().unwrap_or_else(|_| usage()),
+            "--seconds" => args.seconds = value.parse().unwrap_or_else(|_| usage()),
+            "--fps" => args.fps = value.parse().unwrap_or_else(|_| usage()),
+            "--policy" => args.policy = value,
+            "--record" => args.record = Some(value),
+            _ => usage(),
+        }
+    }
+    args
+}
+
+fn main() {
+    let args = parse_args();
+
+    let level = match args.level.as_ref() {
+        Some(path) => Level::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        None => Level::default(),
+    };
+    let mut policy: Box<dyn InputPolicy> = match args.policy.as_str() {
+        "random" => Box::new(RandomPolicy::new(args.seed)),
+        "autopilot" => Box::new(AutopilotPolicy::default()),
+        path => Box::new(ScriptedPolicy::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        })),
+    };
+
+    let mut world = World::new(level, args.seed);
+    match args.record.as_ref() {
+        Some(path) => {
+            let mut recorder = Recorder {
+                policy: policy.as_mut(),
+                replay: Replay::new(args.seed, args.fps),
+            };
+            world.run(&mut recorder, args.seconds, args.fps);
+            if let Err(e) = fs::write(path, recorder.replay.to_string()) {
+                eprintln!("failed to write {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+        None => world.run(policy.as_mut(), args.seconds, args.fps),
+    }
+
+    match serde_json::to_string_pretty(&world.summary()) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("failed to write summary: {}", e);
+            process::exit(1);
+        }
+    }
+}
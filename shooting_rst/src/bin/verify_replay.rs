@@ -0,0 +1,150 @@
+//! Re-simulates the replay of a leaderboard entry recorded on the sim core and checks its
+//! score, kills and duration. Prints the verdict as JSON and exits with 1 when the entry is
+//! rejected. A replay recorded by the Godot game cannot be re-run on the core and is not
+//! checked: the exit code is 3, and the entry is not to be ranked.
+//!
+//! cargo run --no-default-features --features sim --bin verify_replay -- \
+//!     [--level level.json] SUBMISSION
+//! cargo run --no-default-features --features sim --bin verify_replay -- \
+//!     [--level level.json] --replay replay.txt --score N --kills N --seconds S
+//!
+//! SUBMISSION is a file with the body POSTed to /scores, `-` reads it from stdin.
+
+use shooting_rst::leaderboard::Submission;
+use shooting_rst::replay::Replay;
+use shooting_rst::sim::{verify, Claim, Level};
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+struct Args {
+    level: Option<String>,
+    submission: Option<String>,
+    replay: Option<String>,
+    score: Option<i32>,
+    kills: Option<i32>,
+    seconds: Option<f32>,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: verify_replay [--level FILE] SUBMISSION|-\n       \
+         verify_replay [--level FILE] --replay FILE --score N --kills N --seconds S"
+    );
+    process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        level: None,
+        submission: None,
+        replay: None,
+        score: None,
+        kills: None,
+        seconds: None,
+    };
+    let mut it = env::args().skip(1);
+    while let Some(arg) = it.next() {
+        if !arg.starts_with("--") {
+            if args.submission.is_some() {
+                usage();
+            }
+            args.submission = Some(arg);
+            continue;
+        }
+        let value = it.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--level" => args.level = Some(value),
+            "--replay" => args.replay = Some(value),
+            "--score" => args.score = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--kills" => args.kills = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--seconds" => args.seconds = Some(value.parse().unwrap_or_else(|_| usage())),
+            _ => usage(),
+        }
+    }
+    args
+}
+
+fn read_text(path: &str) -> String {
+    let text = if path == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).map(|_| text)
+    } else {
+        fs::read_to_string(path)
+    };
+    text.unwrap_or_else(|e| {
+        eprintln!("failed to read {}: {}", path, e);
+        process::exit(2);
+    })
+}
+
+fn reject(message: &str) -> ! {
+    eprintln!("rejected: {}", message);
+    process::exit(1);
+}
+
+// the replay and what the entry claims for it
+fn entry(args: &Args) -> (Replay, Claim) {
+    match (&args.submission, &args.replay) {
+        (Some(path), None) => {
+            // the seed and hash are checked against the replay here
+            let submission =
+                Submission::from_json(&read_text(path)).unwrap_or_else(|e| reject(&e.to_string()));
+            let replay =
+                Replay::parse(&submission.replay).unwrap_or_else(|e| reject(&e.to_string()));
+            let claim = Claim {
+                score: submission.score,
+                kills: submission.kills,
+                seconds: submission.seconds,
+            };
+            (replay, claim)
+        }
+        (None, Some(path)) => {
+            let (score, kills, seconds) = match (args.score, args.kills, args.seconds) {
+                (Some(score), Some(kills), Some(seconds)) => (score, kills, seconds),
+                _ => usage(),
+            };
+            let replay = Replay::parse(&read_text(path)).unwrap_or_else(|e| reject(&e.to_string()));
+            let claim = Claim {
+                score,
+                kills,
+                seconds,
+            };
+            (replay, claim)
+        }
+        _ => usage(),
+    }
+}
+
+fn main() {
+    let args = parse_args();
+
+    let level = match args.level.as_ref() {
+        Some(path) => Level::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        }),
+        None => Level::default(),
+    };
+    let (replay, claim) = entry(&args);
+
+    let verdict = verify(level, &replay, claim);
+    match serde_json::to_string_pretty(&verdict) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("failed to write verdict: {}", e);
+            process::exit(2);
+        }
+    }
+    if !verdict.verified {
+        eprintln!(
+            "unverified: recorded under the {} rules, nothing was checked",
+            replay.rules.name()
+        );
+        process::exit(3);
+    }
+    if !verdict.accepted {
+        process::exit(1);
+    }
+}
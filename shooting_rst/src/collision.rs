@@ -0,0 +1,179 @@
+//! Rust-side collision pass: circles on the xz plane bucketed into a spatial hash grid.
+//! Does not depend on Godot so it can be benchmarked headless.
+
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Circle {
+    pub x: f32,
+    pub z: f32,
+    pub r: f32,
+}
+
+impl Circle {
+    pub fn new(x: f32, z: f32, r: f32) -> Self {
+        Circle { x, z, r }
+    }
+
+    pub fn overlaps(&self, other: &Circle) -> bool {
+        let dx = self.x - other.x;
+        let dz = self.z - other.z;
+        let r = self.r + other.r;
+        dx * dx + dz * dz <= r * r
+    }
+}
+
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    circles: Vec<Circle>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialHash {
+            cell_size: cell_size.max(0.01),
+            cells: HashMap::new(),
+            circles: Vec::new(),
+        }
+    }
+
+    fn cell_of(&self, v: f32) -> i32 {
+        (v / self.cell_size).floor() as i32
+    }
+
+    /// Removes every circle but keeps the allocated cells for the next tick.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.circles.clear();
+    }
+
+    /// Adds a circle and returns its index, which `query` reports back.
+    pub fn insert(&mut self, circle: Circle) -> usize {
+        let id = self.circles.len();
+        self.circles.push(circle);
+        for cx in self.cell_of(circle.x - circle.r)..=self.cell_of(circle.x + circle.r) {
+            for cz in self.cell_of(circle.z - circle.r)..=self.cell_of(circle.z + circle.r) {
+                self.cells.entry((cx, cz)).or_default().push(id);
+            }
+        }
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.circles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.circles.is_empty()
+    }
+
+    /// Pushes the indexes of circles overlapping `circle` into `out`, each once.
+    pub fn query(&self, circle: &Circle, out: &mut Vec<usize>) {
+        let start = out.len();
+        for cx in self.cell_of(circle.x - circle.r)..=self.cell_of(circle.x + circle.r) {
+            for cz in self.cell_of(circle.z - circle.r)..=self.cell_of(circle.z + circle.r) {
+                let cell = match self.cells.get(&(cx, cz)) {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+                for &id in cell {
+                    if self.circles[id].overlaps(circle) && !out[start..].contains(&id) {
+                        out.push(id);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Hashes `bullets` and tests every target against them.
+/// Returns `(bullet index, target index)` pairs.
+pub fn collide(
+    grid: &mut SpatialHash,
+    bullets: &[Circle],
+    targets: &[Circle],
+) -> Vec<(usize, usize)> {
+    grid.clear();
+    for b in bullets {
+        grid.insert(*b);
+    }
+    let mut hits = Vec::new();
+    let mut found = Vec::new();
+    for (t, target) in targets.iter().enumerate() {
+        found.clear();
+        grid.query(target, &mut found);
+        hits.extend(found.iter().map(|&b| (b, t)));
+    }
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(bullets: &[Circle], targets: &[Circle]) -> Vec<(usize, usize)> {
+        let mut hits = Vec::new();
+        for (t, target) in targets.iter().enumerate() {
+            for (b, bullet) in bullets.iter().enumerate() {
+                if bullet.overlaps(target) {
+                    hits.push((b, t));
+                }
+            }
+        }
+        hits
+    }
+
+    #[test]
+    fn query_reports_a_circle_spanning_cells_once() {
+        let mut grid = SpatialHash::new(1.0);
+        let big = grid.insert(Circle::new(0.0, 0.0, 2.5));
+        let mut found = Vec::new();
+        grid.query(&Circle::new(0.5, 0.5, 3.0), &mut found);
+        assert_eq!(found, vec![big]);
+    }
+
+    #[test]
+    fn query_finds_neighbours_across_cell_borders() {
+        let mut grid = SpatialHash::new(1.0);
+        let left = grid.insert(Circle::new(-0.05, -0.05, 0.1));
+        grid.insert(Circle::new(3.0, 3.0, 0.1));
+        let mut found = Vec::new();
+        grid.query(&Circle::new(0.05, 0.05, 0.1), &mut found);
+        assert_eq!(found, vec![left]);
+    }
+
+    #[test]
+    fn clear_empties_the_grid() {
+        let mut grid = SpatialHash::new(1.0);
+        grid.insert(Circle::new(0.0, 0.0, 1.0));
+        grid.clear();
+        assert!(grid.is_empty());
+        let mut found = Vec::new();
+        grid.query(&Circle::new(0.0, 0.0, 1.0), &mut found);
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn collide_matches_brute_force() {
+        // deterministic scatter over a field larger than a few cells
+        let mut seed = 12345u32;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 8) as f32 / (1 << 24) as f32
+        };
+        let bullets: Vec<Circle> = (0..300)
+            .map(|_| Circle::new(next() * 40.0 - 20.0, next() * 40.0 - 20.0, 0.25))
+            .collect();
+        let targets: Vec<Circle> = (0..50)
+            .map(|_| Circle::new(next() * 40.0 - 20.0, next() * 40.0 - 20.0, 0.9))
+            .collect();
+        let mut grid = SpatialHash::new(2.0);
+        let mut hits = collide(&mut grid, &bullets, &targets);
+        hits.sort_unstable_by_key(|&(b, t)| (t, b));
+        assert!(!hits.is_empty());
+        assert_eq!(hits, brute_force(&bullets, &targets));
+    }
+}
@@ -0,0 +1,412 @@
+//! Typed gameplay events, written one JSON object per line.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+
+use crate::barrage::barrage_name;
+use crate::json;
+
+/// Where an alien bullet came from, carried by the bullet until it hits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BulletOrigin {
+    pub barrage: usize,
+    /// serial of the volley, shared by the bullets fired together
+    pub volley: u64,
+    pub spawn_tick: u64,
+    pub alien_kind: String,
+    pub alien_id: i64,
+}
+
+/// What took a life.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum DeathCause {
+    Alien {
+        kind: String,
+        id: i64,
+    },
+    Bullet(BulletOrigin),
+    #[default]
+    Unknown,
+}
+
+impl DeathCause {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeathCause::Alien { .. } => "alien",
+            DeathCause::Bullet(_) => "alien_bullet",
+            DeathCause::Unknown => "unknown",
+        }
+    }
+
+    /// For the game over screen, "shot down by Three-Way from alien #12".
+    pub fn describe(&self) -> String {
+        match self {
+            DeathCause::Alien { kind, id } => format!("rammed by {} #{}", kind, id),
+            DeathCause::Bullet(origin) => format!(
+                "shot down by {} from {} #{}",
+                barrage_name(origin.barrage),
+                origin.alien_kind,
+                origin.alien_id
+            ),
+            DeathCause::Unknown => "shot down".to_string(),
+        }
+    }
+
+    /// `"cause"` and the fields that go with it, each with a leading comma.
+    pub(crate) fn json_fields(&self) -> String {
+        let mut out = format!(r#","cause":{}"#, json::string(self.name()));
+        match self {
+            DeathCause::Alien { kind, id } => {
+                let _ = write!(
+                    out,
+                    r#","alien_kind":{},"alien_id":{}"#,
+                    json::string(kind),
+                    id
+                );
+            }
+            DeathCause::Bullet(origin) => {
+                let _ = write!(
+                    out,
+                    r#","barrage":{},"barrage_name":{},"volley":{},"spawn_tick":{},"alien_kind":{},"alien_id":{}"#,
+                    origin.barrage,
+                    json::string(barrage_name(origin.barrage)),
+                    origin.volley,
+                    origin.spawn_tick,
+                    json::string(&origin.alien_kind),
+                    origin.alien_id
+                );
+            }
+            DeathCause::Unknown => {}
+        }
+        out
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    GameStarted {
+        seed: u64,
+        players: usize,
+        demo: bool,
+    },
+    GameOver {
+        scores: Vec<i32>,
+    },
+    AlienSpawned {
+        id: i64,
+        kind: String,
+        pattern: &'static str,
+        x: f32,
+        z: f32,
+    },
+    AlienKilled {
+        player: usize,
+        x: f32,
+        z: f32,
+    },
+    PlayerHit {
+        player: usize,
+        life: i32,
+        cause: DeathCause,
+    },
+    Extend {
+        player: usize,
+        life: i32,
+    },
+    HeatChanged {
+        heat: f32,
+    },
+    PoolExhausted {
+        pool: String,
+    },
+    ContinueTaken {
+        count: i32,
+    },
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::GameStarted { .. } => "GameStarted",
+            Event::GameOver { .. } => "GameOver",
+            Event::AlienSpawned { .. } => "AlienSpawned",
+            Event::AlienKilled { .. } => "AlienKilled",
+            Event::PlayerHit { .. } => "PlayerHit",
+            Event::Extend { .. } => "Extend",
+            Event::HeatChanged { .. } => "HeatChanged",
+            Event::PoolExhausted { .. } => "PoolExhausted",
+            Event::ContinueTaken { .. } => "ContinueTaken",
+        }
+    }
+}
+
+/// An event with the physics tick and the engine clock it happened at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub tick: u64,
+    pub msec: u64,
+    pub event: Event,
+}
+
+impl Record {
+    /// `{"tick":120,"msec":2034,"event":"AlienKilled","player":0,"x":1.5,"z":-8}`
+    pub fn to_json(&self) -> String {
+        let mut out = format!(
+            r#"{{"tick":{},"msec":{},"event":"{}""#,
+            self.tick,
+            self.msec,
+            self.event.name()
+        );
+        let mut field = |key: &str, value: String| {
+            let _ = write!(out, r#","{}":{}"#, key, value);
+        };
+        match &self.event {
+            Event::GameStarted {
+                seed,
+                players,
+                demo,
+            } => {
+                field("seed", seed.to_string());
+                field("players", players.to_string());
+                field("demo", demo.to_string());
+            }
+            Event::GameOver { scores } => {
+                let scores: Vec<_> = scores.iter().map(|s| s.to_string()).collect();
+                field("scores", format!("[{}]", scores.join(",")));
+            }
+            Event::AlienSpawned {
+                id,
+                kind,
+                pattern,
+                x,
+                z,
+            } => {
+                field("id", id.to_string());
+                field("kind", json::string(kind));
+                field("pattern", json::string(pattern));
+                field("x", json::float(*x));
+                field("z", json::float(*z));
+            }
+            Event::AlienKilled { player, x, z } => {
+                field("player", player.to_string());
+                field("x", json::float(*x));
+                field("z", json::float(*z));
+            }
+            Event::PlayerHit {
+                player,
+                life,
+                cause,
+            } => {
+                field("player", player.to_string());
+                field("life", life.to_string());
+                out.push_str(&cause.json_fields());
+            }
+            Event::Extend { player, life } => {
+                field("player", player.to_string());
+                field("life", life.to_string());
+            }
+            Event::HeatChanged { heat } => field("heat", json::float(*heat)),
+            Event::PoolExhausted { pool } => field("pool", json::string(pool)),
+            Event::ContinueTaken { count } => field("count", count.to_string()),
+        }
+        out.push('}');
+        out
+    }
+}
+
+/// Keeps the latest `capacity` records, to dump when something went wrong.
+pub struct EventRing {
+    records: VecDeque<Record>,
+    capacity: usize,
+}
+
+impl EventRing {
+    pub fn new(capacity: usize) -> Self {
+        EventRing {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, record: Record) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() >= self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Record> {
+        self.records.iter()
+    }
+
+    /// Oldest first, one line each.
+    pub fn to_json_lines(&self) -> String {
+        let mut out = String::new();
+        for r in self.records.iter() {
+            out.push_str(&r.to_json());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(tick: u64, event: Event) -> Record {
+        Record {
+            tick,
+            msec: tick * 16,
+            event,
+        }
+    }
+
+    fn heat(tick: u64) -> Record {
+        record(tick, Event::HeatChanged { heat: tick as f32 })
+    }
+
+    fn parse(line: &str) -> serde_json::Value {
+        serde_json::from_str(line).unwrap()
+    }
+
+    fn bullet() -> DeathCause {
+        DeathCause::Bullet(BulletOrigin {
+            barrage: 4,
+            volley: 7,
+            spawn_tick: 300,
+            alien_kind: "alien".to_string(),
+            alien_id: 12,
+        })
+    }
+
+    // the cause fields in an object of their own
+    fn cause_fields(cause: &DeathCause) -> serde_json::Value {
+        parse(&format!(r#"{{"player":0{}}}"#, cause.json_fields()))
+    }
+
+    #[test]
+    fn records_escape_their_strings() {
+        let pool = "pool \"a\"\\b\n\t\u{1}";
+        let line = record(
+            3,
+            Event::PoolExhausted {
+                pool: pool.to_string(),
+            },
+        )
+        .to_json();
+        assert!(!line.contains('\n') && !line.contains('\u{1}'));
+        let value = parse(&line);
+        assert_eq!(value["event"], "PoolExhausted");
+        assert_eq!(value["tick"], 3);
+        assert_eq!(value["pool"], pool);
+    }
+
+    #[test]
+    fn records_write_no_nan() {
+        let line = record(
+            1,
+            Event::AlienKilled {
+                player: 0,
+                x: f32::NAN,
+                z: -8.0,
+            },
+        )
+        .to_json();
+        let value = parse(&line);
+        assert!(value["x"].is_null());
+        assert_eq!(value["z"], -8.0);
+    }
+
+    #[test]
+    fn causes_describe_themselves() {
+        let alien = DeathCause::Alien {
+            kind: "alien".to_string(),
+            id: 3,
+        };
+        assert_eq!(alien.describe(), "rammed by alien #3");
+        assert_eq!(bullet().describe(), "shot down by Three-Way from alien #12");
+        assert_eq!(DeathCause::Unknown.describe(), "shot down");
+    }
+
+    #[test]
+    fn causes_write_their_fields() {
+        let alien = cause_fields(&DeathCause::Alien {
+            kind: "alien".to_string(),
+            id: 3,
+        });
+        assert_eq!(alien["cause"], "alien");
+        assert_eq!(alien["alien_kind"], "alien");
+        assert_eq!(alien["alien_id"], 3);
+        assert!(alien.get("barrage").is_none());
+
+        let shot = cause_fields(&bullet());
+        assert_eq!(shot["cause"], "alien_bullet");
+        assert_eq!(shot["barrage"], 4);
+        assert_eq!(shot["barrage_name"], "Three-Way");
+        assert_eq!(shot["volley"], 7);
+        assert_eq!(shot["spawn_tick"], 300);
+        assert_eq!(shot["alien_kind"], "alien");
+        assert_eq!(shot["alien_id"], 12);
+
+        let unknown = cause_fields(&DeathCause::Unknown);
+        assert_eq!(unknown["cause"], "unknown");
+        assert_eq!(unknown.as_object().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn player_hits_carry_their_cause() {
+        let line = record(
+            9,
+            Event::PlayerHit {
+                player: 1,
+                life: 2,
+                cause: bullet(),
+            },
+        )
+        .to_json();
+        let value = parse(&line);
+        assert_eq!(value["event"], "PlayerHit");
+        assert_eq!(value["life"], 2);
+        assert_eq!(value["cause"], "alien_bullet");
+        assert_eq!(value["volley"], 7);
+    }
+
+    #[test]
+    fn ring_keeps_the_latest_records() {
+        let mut ring = EventRing::new(3);
+        for tick in 0..5 {
+            ring.push(heat(tick));
+        }
+        assert_eq!(ring.len(), 3);
+        let ticks: Vec<_> = ring.iter().map(|r| r.tick).collect();
+        assert_eq!(ticks, [2, 3, 4]);
+
+        let mut off = EventRing::new(0);
+        off.push(heat(0));
+        assert!(off.is_empty());
+    }
+
+    #[test]
+    fn ring_dumps_oldest_first() {
+        let mut ring = EventRing::new(4);
+        for tick in 0..6 {
+            ring.push(heat(tick));
+        }
+        let dump = ring.to_json_lines();
+        assert!(dump.ends_with('\n'));
+        let ticks: Vec<_> = dump.lines().map(|l| parse(l)["tick"].clone()).collect();
+        assert_eq!(ticks, [2, 3, 4, 5]);
+    }
+}
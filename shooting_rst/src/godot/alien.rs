@@ -0,0 +1,593 @@
+use gdnative::api::{Area, AudioStreamPlayer, CPUParticles, CollisionShape, RandomNumberGenerator};
+use gdnative::prelude::*;
+use std::f32::consts::PI;
+
+use super::bullet::Bullet;
+use super::layer::{classify_hit, validate_layers, HitKind, Layer};
+use super::magazine::Cartridge;
+use super::play_field::PlayField;
+use super::player::nearest_living_player;
+use super::{rotate_xz_vec3, AlienEnv, Env, ALIEN_GROUP};
+use crate::barrage::{BARRAGE_KINDS, BULLET_SPEED_RATIO};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum AlienProcessPattern {
+    Default,
+    Invasion,
+    Dir,
+}
+
+impl AlienProcessPattern {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlienProcessPattern::Default => "default",
+            AlienProcessPattern::Invasion => "invasion",
+            AlienProcessPattern::Dir => "dir",
+        }
+    }
+}
+
+#[derive(NativeClass)]
+#[inherit(Area)]
+#[register_with(Self::register_signals)]
+pub struct Alien {
+    pub alive: bool,
+    #[property(default = 5.0)]
+    speed: f32,
+    direction: Vector3,
+    setted_speed: f32,
+    env: Env,
+    attack_sound: Option<Ref<AudioStreamPlayer, Unique>>,
+    collision_shape: Option<Ref<CollisionShape, Unique>>,
+    alien_spatial: Option<Ref<Spatial, Unique>>,
+    frag: Option<Ref<CPUParticles, Unique>>,
+    destruct_timer: Option<Ref<Timer, Unique>>,
+    alien_env: AlienEnv,
+    // process_pattern: fn(&mut Self, &Area, f64),
+    process_pattern: AlienProcessPattern,
+    rng: Option<Ref<RandomNumberGenerator, Unique>>,
+    change_dir_timer: Option<Ref<Timer, Unique>>,
+
+    fire_timer: Option<Ref<Timer, Unique>>,
+
+    #[property(default = 5.0)]
+    default_min_fire_interval: f32,
+    #[property(default = 10.0)]
+    default_max_fire_interval: f32,
+
+    // set by the Stage on spawn, for death causes
+    pub id: i64,
+    pub kind: String,
+    generation: u64,
+}
+
+#[gdnative::methods]
+impl Alien {
+    fn new(_owner: &Area) -> Self {
+        Self {
+            alive: false,
+            speed: 5.0,
+            direction: Vector3::new(0.0, 0.0, -1.0),
+            setted_speed: 5.0,
+            env: Env::new(),
+            alien_env: AlienEnv::new(),
+            attack_sound: None,
+            collision_shape: None,
+            alien_spatial: None,
+            frag: None,
+            destruct_timer: None,
+            process_pattern: AlienProcessPattern::Default,
+            rng: None,
+            change_dir_timer: None,
+
+            fire_timer: None,
+
+            default_min_fire_interval: 5.0,
+            default_max_fire_interval: 10.0,
+
+            id: 0,
+            kind: String::new(),
+            generation: 0,
+        }
+    }
+
+    fn register_signals(builder: &ClassBuilder<Self>) {
+        // godot_print!("register_signals@Alien");
+
+        builder.add_signal(Signal {
+            name: "collect_alien",
+            args: &[
+                SignalArgument {
+                    name: "alien",
+                    default: Variant::new(),
+                    export_info: ExportInfo::new(VariantType::Object),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "generation",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "beated_alien",
+            args: &[
+                SignalArgument {
+                    name: "player",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "pos",
+                    default: Variant::from_vector3(&Vector3::new(0.0, 0.0, 0.0)),
+                    export_info: ExportInfo::new(VariantType::Vector3),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "alien_fire",
+            args: &[
+                SignalArgument {
+                    name: "pos",
+                    default: Variant::from_vector3(&Vector3::new(0.0, 0.0, 0.0)),
+                    export_info: ExportInfo::new(VariantType::Vector3),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "dir",
+                    default: Variant::from_vector3(&Vector3::new(0.0, 0.0, 0.0)),
+                    export_info: ExportInfo::new(VariantType::Vector3),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "speed",
+                    default: Variant::from_f64(0.0),
+                    export_info: ExportInfo::new(VariantType::F64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "bullet_type",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "alien_id",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "alien_kind",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+
+        // godot_print!("end register_signals@Alien");
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Area) {
+        // godot_print!("start _ready@Alien");
+        validate_layers(owner, Layer::ALIEN, Layer::PLAYER | Layer::BULLET);
+        if self.rng.is_none() {
+            let rng = RandomNumberGenerator::new();
+            rng.randomize();
+            self.rng = Some(rng);
+        }
+
+        self.alive = true;
+        self.setted_speed = self.speed;
+        // self.direction = Vector3::new(0.0, 0.0, 1.0);
+
+        let stage = unsafe { owner.get_node("/root/stage").unwrap().assume_safe() };
+        owner
+            .connect(
+                "collect_alien",
+                stage,
+                "collect_alien",
+                VariantArray::new_shared(),
+                0,
+            )
+            .unwrap();
+        owner
+            .connect(
+                "beated_alien",
+                stage,
+                "alien_beated",
+                VariantArray::new_shared(),
+                0,
+            )
+            .unwrap();
+        owner
+            .connect(
+                "alien_fire",
+                stage,
+                "alien_fire",
+                VariantArray::new_shared(),
+                0,
+            )
+            .unwrap();
+
+        owner.add_to_group(ALIEN_GROUP, false);
+
+        unsafe {
+            self.frag = Some(
+                owner
+                    .get_node_as::<CPUParticles>("frag")
+                    .unwrap()
+                    .claim()
+                    .assume_unique(),
+            );
+            self.attack_sound = Some(
+                owner
+                    .get_node_as::<AudioStreamPlayer>("attackSound")
+                    .unwrap()
+                    .claim()
+                    .assume_unique(),
+            );
+            self.alien_spatial = Some(
+                owner
+                    .get_node_as::<Spatial>("alien1")
+                    .unwrap()
+                    .claim()
+                    .assume_unique(),
+            );
+            self.collision_shape = Some(
+                owner
+                    .get_node_as::<CollisionShape>("CollisionShape")
+                    .unwrap()
+                    .claim()
+                    .assume_unique(),
+            );
+            self.destruct_timer = Some(
+                owner
+                    .get_node_as::<Timer>("DestructTimer")
+                    .unwrap()
+                    .claim()
+                    .assume_unique(),
+            );
+            self.change_dir_timer = Some(
+                owner
+                    .get_node_as::<Timer>("ChangeDirTimer")
+                    .unwrap()
+                    .claim()
+                    .assume_unique(),
+            );
+            self.fire_timer = Some(
+                owner
+                    .get_node_as::<Timer>("FireTimer")
+                    .unwrap()
+                    .claim()
+                    .assume_unique(),
+            );
+        }
+
+        self.set_dir_change_span_random(1.0);
+        self.set_fire_span_random(1.0);
+        // godot_print!("_ready@Alien {}", env!("CARGO_PKG_VERSION"));
+    }
+
+    /// Set by the Stage before the alien enters the tree.
+    pub fn set_play_field(&mut self, field: &PlayField) {
+        self.env.init(field);
+        self.alien_env.init(field);
+    }
+
+    pub fn reset(&mut self, _owner: &Area) {
+        self.alive = true;
+        self.speed = self.setted_speed;
+        if let Some(s) = self.alien_spatial.as_ref() {
+            s.set_visible(true);
+            // godot_print!("reset visibility");
+        }
+        if let Some(c) = self.collision_shape.as_ref() {
+            c.set_disabled(false);
+            // godot_print!("reset collision");
+        }
+    }
+
+    pub fn speed_up(&mut self, times: f32) {
+        self.speed *= times;
+    }
+
+    pub fn set_dir(&mut self, dir: Vector3) {
+        // godot_print!("pre dir : {:?}", dir);
+        self.direction = dir.normalize();
+        if !self.direction.is_finite() {
+            self.direction = Vector3::new(0.0, 0.0, -1.0);
+        }
+        // godot_print!("post dir : {:?}", self.direction);
+    }
+
+    #[export]
+    fn change_dir_random(&mut self, _owner: &Area) {
+        // godot_print!("change_dir_random");
+
+        if self.process_pattern != AlienProcessPattern::Dir {
+            return;
+        }
+
+        let rng = match self.rng.as_ref() {
+            Some(r) => r,
+            None => return,
+        };
+        let theta = rng.randf_range((-PI / 4.0) as _, (PI / 4.0) as _);
+        self.set_dir(rotate_xz_vec3(self.direction, theta as _));
+    }
+
+    pub fn set_dir_change_span_random(&mut self, ratio: f32) {
+        let span = if let Some(rng) = self.rng.as_ref() {
+            rng.randf_range(0.0, 3.0)
+        } else {
+            1.0
+        };
+        if let Some(t) = self.change_dir_timer.as_ref() {
+            t.set_wait_time(span * ratio as f64);
+            // godot_print!("reset change_dir_timer");
+        }
+    }
+
+    pub fn set_fire_span_random(&mut self, ratio: f32) {
+        // godot_print!("@@ set_fire_span_random");
+        let span = if let Some(rng) = self.rng.as_ref() {
+            rng.randf_range(
+                self.default_min_fire_interval as _,
+                self.default_max_fire_interval as _,
+            )
+        } else {
+            1.0
+        };
+        if let Some(t) = self.fire_timer.as_ref() {
+            // godot_print!("set to {}", span * ratio as f64);
+            t.set_wait_time(span * ratio as f64);
+            // godot_print!("reset fire_timer");
+        }
+    }
+
+    #[export]
+    fn timer_start(&self, _owner: &Area) {
+        if let Some(t) = self.change_dir_timer.as_ref() {
+            // godot_print!("change_dir_timer_start {}", t.wait_time());
+            t.start(0.0);
+            // godot_print!("start change_dir_timer");
+        }
+        if let Some(t) = self.fire_timer.as_ref() {
+            // godot_print!("fire_timer_start {}", t.wait_time());
+            t.start(0.0);
+            // godot_print!("start fire_timer");
+        }
+    }
+
+    #[export]
+    fn _on_alien_tree_entered(&self, owner: &Area) {
+        // godot_print!("_on_alien_tree_entered");
+        unsafe {
+            owner.call_deferred("timer_start", &[]);
+        }
+    }
+
+    #[export]
+    fn timer_stop(&self, _owner: &Area) {
+        // godot_print!("change_dir_timer_stop");
+        if let Some(t) = self.change_dir_timer.as_ref() {
+            t.stop();
+            // godot_print!("stop change_dir_timer");
+        }
+        if let Some(t) = self.fire_timer.as_ref() {
+            t.stop();
+            // godot_print!("stop fire_timer");
+        }
+        if let Some(t) = self.destruct_timer.as_ref() {
+            t.stop();
+        }
+    }
+
+    pub fn set_process(&mut self, process: AlienProcessPattern) {
+        // godot_print!("set_process");
+        self.process_pattern = process;
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Area, delta: f64) {
+        let f: fn(&mut Alien, &Area, f64) = match self.process_pattern {
+            AlienProcessPattern::Default => Alien::default_process_pattern,
+            AlienProcessPattern::Invasion => Alien::invasion_pattern,
+            AlienProcessPattern::Dir => Alien::dir_pattern,
+        };
+        (f)(self, owner, delta);
+    }
+
+    pub fn seed_rng(&mut self, seed: i64) {
+        self.rng
+            .get_or_insert_with(RandomNumberGenerator::new)
+            .set_seed(seed);
+    }
+
+    /// `None` once beaten.
+    pub fn velocity(&self) -> Option<Vector3> {
+        if !self.alive {
+            return None;
+        }
+        Some(match self.process_pattern {
+            AlienProcessPattern::Default => Vector3::new(self.speed, 0.0, 0.0),
+            AlienProcessPattern::Invasion => Vector3::new(0.0, 0.0, self.speed),
+            AlienProcessPattern::Dir => self.direction * self.speed,
+        })
+    }
+
+    pub fn default_process_pattern(&mut self, owner: &Area, delta: f64) {
+        let d = Vector3::new(self.speed * delta as f32, 0.0, 0.0);
+        owner.translate(d);
+
+        if (owner.translation().x < self.env.left_limit && self.speed < 0.0)
+            || (owner.translation().x > self.env.right_limit && self.speed > 0.0)
+        {
+            self.speed *= -1.0;
+        }
+    }
+
+    pub fn invasion_pattern(&mut self, owner: &Area, delta: f64) {
+        let d = Vector3::new(0.0, 0.0, self.speed * delta as f32);
+        owner.translate(d);
+
+        if self.alien_env.gone_far_away(owner) {
+            self.destruct(owner);
+        }
+    }
+
+    pub fn dir_pattern(&mut self, owner: &Area, delta: f64) {
+        let d = self.direction * self.speed * delta as f32;
+        owner.translate(d);
+
+        if self.alien_env.gone_far_away(owner) {
+            self.destruct(owner);
+        }
+    }
+
+    #[export]
+    fn shooted(&mut self, owner: &Area, area: Variant) {
+        if !self.alive {
+            return;
+        }
+
+        let area: TRef<Area, _> = match area.try_to_object::<Area>() {
+            Some(area) => unsafe { area.assume_safe() },
+            None => return,
+        };
+        if classify_hit(&area) != HitKind::PlayerBullet {
+            return;
+        }
+        let player = Instance::<Bullet, _>::from_base(area.claim())
+            .and_then(|b| unsafe { b.assume_safe() }.map(|b, _| b.player_index).ok())
+            .unwrap_or(0);
+
+        // godot_print!("shooted@Alien");
+
+        self.speed = 0.0;
+        self.alive = false;
+        self.frag.as_ref().unwrap().set_emitting(true);
+        self.attack_sound.as_ref().unwrap().play(0.0);
+        self.alien_spatial.as_ref().unwrap().set_visible(false);
+        // self.collision_shape.as_ref().unwrap().set_disabled(true);
+        self.destruct_timer.as_ref().unwrap().start(0.0);
+
+        owner.emit_signal(
+            "beated_alien",
+            &[
+                Variant::from_i64(player as i64),
+                Variant::from_vector3(&owner.translation()),
+            ],
+        );
+
+        unsafe {
+            owner.call_deferred("disable_collision", &[]);
+        }
+    }
+
+    #[export]
+    fn disable_collision(&mut self, _owner: &Area) {
+        // recycled and spawned again since it was shot
+        if self.alive {
+            return;
+        }
+        // godot_print!("disable_collision@Alien");
+        self.collision_shape.as_ref().unwrap().set_disabled(true);
+        // godot_print!("end disable_collision@Alien");
+    }
+
+    #[export]
+    fn destruct(&mut self, owner: &Area) {
+        self.alive = false;
+        // self.change_dir_timer_stop(owner);
+        unsafe {
+            owner.call_deferred("return_to_base", &[Variant::from_u64(self.generation)]);
+        }
+    }
+
+    #[export]
+    fn return_to_base(&self, owner: &Area, generation: u64) {
+        // godot_print!("return_to_base");
+        // recycled and spawned again since it was destructed
+        if generation != self.generation {
+            return;
+        }
+        let parent = match owner.get_parent() {
+            Some(parent) => parent,
+            // already collected
+            None => return,
+        };
+        unsafe {
+            parent.assume_safe().remove_child(owner.assume_shared());
+        }
+        let area = unsafe { owner.assume_unique() };
+        owner.emit_signal(
+            "collect_alien",
+            &[Variant::from_object(area), Variant::from_u64(generation)],
+        );
+        // godot_print!("end collect alien");
+    }
+
+    #[export]
+    fn fire(&self, owner: &Area) {
+        if !self.alive {
+            return;
+        }
+
+        let rng = match self.rng.as_ref() {
+            Some(rng) => rng,
+            None => return,
+        };
+
+        // godot_print!("fire@Alien");
+        let pos = owner.translation();
+        let target = if rng.randi_range(0, 1) == 0 {
+            None
+        } else {
+            nearest_living_player(owner, pos)
+        };
+        let dir = match target {
+            // return self to player
+            Some(player_pos) => (player_pos - pos).normalize(),
+            // return its direction
+            None => self.direction,
+        };
+        // godot_print!("{:?} | fire dir {:?}", self.direction, dir);
+        let speed = self.speed * BULLET_SPEED_RATIO;
+        let max_index = BARRAGE_KINDS - 1;
+        let kind = rng.randi_range(0, max_index as i64);
+
+        owner.emit_signal(
+            "alien_fire",
+            &[
+                Variant::from_vector3(&pos),
+                Variant::from_vector3(&dir),
+                Variant::from_f64(speed as f64),
+                Variant::from_i64(kind),
+                Variant::from_i64(self.id),
+                Variant::from_str(&self.kind),
+            ],
+        );
+    }
+}
+
+impl Cartridge for Alien {
+    fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    fn unload(&mut self, owner: TRef<Area>) {
+        self.alive = false;
+        self.timer_stop(&owner);
+        if let Some(f) = self.frag.as_ref() {
+            f.set_emitting(false);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use gdnative::api::Area;
+use gdnative::prelude::*;
+
+use super::layer::{classify_hit_variant, validate_layers, HitKind, Layer};
+use super::magazine::Cartridge;
+use super::play_field::PlayField;
+use super::{advance_xz, AlienEnv, ALIEN_BULLET_GROUP};
+use crate::events::BulletOrigin;
+
+#[derive(NativeClass)]
+#[inherit(Area)]
+#[register_with(Self::register_signals)]
+pub struct AlienBullet {
+    #[property(default = 5.0)]
+    speed: f32,
+    direction: Vector3,
+    pub flying: bool,
+    pub origin: Option<BulletOrigin>,
+
+    alien_env: AlienEnv,
+    generation: u64,
+}
+
+#[gdnative::methods]
+impl AlienBullet {
+    fn new(_owner: &Area) -> Self {
+        Self {
+            speed: 5.0,
+            direction: Vector3::new(0.0, 0.0, 1.0),
+            flying: false,
+            origin: None,
+
+            alien_env: AlienEnv::new(),
+            generation: 0,
+        }
+    }
+
+    fn register_signals(builder: &ClassBuilder<Self>) {
+        // godot_print!("register_signals@AlienBullet");
+
+        builder.add_signal(Signal {
+            name: "collect_alien_bullet",
+            args: &[
+                SignalArgument {
+                    name: "bullet",
+                    default: Variant::new(),
+                    export_info: ExportInfo::new(VariantType::Object),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "generation",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+
+        // godot_print!("end register_signals@AlienBullet");
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Area) {
+        let stage = unsafe { owner.get_node("/root/stage/").unwrap().assume_safe() };
+        owner
+            .connect(
+                "collect_alien_bullet",
+                stage,
+                "collect_alien_bullet",
+                VariantArray::new_shared(),
+                0,
+            )
+            .unwrap();
+        validate_layers(owner, Layer::ALIEN_BULLET, Layer::PLAYER);
+        owner.add_to_group(ALIEN_BULLET_GROUP, false);
+
+        // godot_print!("ready@AlienBullet");
+    }
+
+    pub fn set_play_field(&mut self, field: &PlayField) {
+        self.alien_env.init(field);
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    pub fn set_dir(&mut self, dir: Vector3) {
+        self.direction = dir.normalize();
+        if !self.direction.is_finite() {
+            self.direction = Vector3::new(0.0, 0.0, 1.0);
+        }
+    }
+
+    /// `None` while waiting in the magazine.
+    pub fn velocity(&self) -> Option<Vector3> {
+        if self.flying {
+            Some(self.direction * self.speed)
+        } else {
+            None
+        }
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Area, delta: f64) {
+        owner.set_translation(advance_xz(
+            owner.translation(),
+            self.direction * self.speed,
+            delta as f32,
+        ));
+
+        if self.alien_env.gone_far_away(owner) {
+            self.destruct(owner);
+        }
+    }
+
+    #[export]
+    fn hit(&mut self, owner: &Area, area: Variant) {
+        if classify_hit_variant(&area) == HitKind::Player {
+            self.destruct(owner);
+        }
+    }
+
+    #[export]
+    fn vanish(&mut self, owner: &Area) {
+        self.destruct(owner);
+    }
+
+    fn destruct(&mut self, owner: &Area) {
+        if !self.flying {
+            return;
+        }
+        unsafe {
+            owner.call_deferred("cartridge_fallen", &[Variant::from_u64(self.generation)]);
+        }
+        self.flying = false;
+    }
+
+    #[export]
+    fn cartridge_fallen(&self, owner: &Area, generation: u64) {
+        // recycled and fired again since it fell
+        if generation != self.generation {
+            return;
+        }
+        // a second fall of the same shot finds it collected, the magazine ignores the repeat
+        if let Some(parent) = owner.get_parent() {
+            unsafe {
+                parent.assume_safe().remove_child(owner.assume_shared());
+            }
+        }
+        let area = unsafe { owner.assume_unique() };
+        owner.emit_signal(
+            "collect_alien_bullet",
+            &[Variant::from_object(area), Variant::from_u64(generation)],
+        );
+    }
+}
+
+impl Cartridge for AlienBullet {
+    fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    fn unload(&mut self, _owner: TRef<Area>) {
+        self.flying = false;
+        self.origin = None;
+    }
+}
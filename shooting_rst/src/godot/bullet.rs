@@ -0,0 +1,135 @@
+use gdnative::api::Area;
+use gdnative::prelude::*;
+
+use super::layer::{classify_hit_variant, validate_layers, HitKind, Layer};
+use super::magazine::Cartridge;
+use super::PLAYER_BULLET_GROUP;
+
+#[derive(NativeClass)]
+#[inherit(Area)]
+#[register_with(Self::register_signals)]
+pub struct Bullet {
+    #[property(default = 5.0)]
+    speed: f32,
+    pub flying: bool,
+    pub player_index: i32,
+    pub shooter: Option<Ref<Area, Shared>>,
+    generation: u64,
+}
+
+#[gdnative::methods]
+impl Bullet {
+    fn new(_owner: &Area) -> Self {
+        Self {
+            speed: 5.0,
+            flying: false,
+            player_index: 0,
+            shooter: None,
+            generation: 0,
+        }
+    }
+
+    fn register_signals(builder: &ClassBuilder<Self>) {
+        // godot_print!("register_signals@Bullet");
+
+        builder.add_signal(Signal {
+            name: "collect",
+            args: &[
+                SignalArgument {
+                    name: "bullet",
+                    default: Variant::new(),
+                    export_info: ExportInfo::new(VariantType::Object),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "generation",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+
+        // godot_print!("end register_signals@Bullet");
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Area) {
+        // bullets always go back to the magazine of the player who fired them first
+        let player_root = match self.shooter.as_ref() {
+            Some(shooter) => unsafe { shooter.assume_safe() },
+            None => {
+                godot_error!("bullet entered the tree without a shooter");
+                return;
+            }
+        };
+        validate_layers(owner, Layer::BULLET, Layer::ALIEN);
+        owner.add_to_group(PLAYER_BULLET_GROUP, false);
+        owner
+            .connect(
+                "collect",
+                player_root,
+                "collect_bullet",
+                VariantArray::new_shared(),
+                0,
+            )
+            .unwrap();
+
+        // godot_print!("ready@Bullet");
+    }
+
+    #[export]
+    fn _physics_process(&self, owner: &Area, delta: f64) {
+        let d = Vector3::new(0.0, 0.0, -self.speed * delta as f32);
+        owner.translate(d);
+    }
+
+    #[export]
+    fn hit(&mut self, owner: &Area, area: Variant) {
+        match classify_hit_variant(&area) {
+            HitKind::Alien | HitKind::Wall => self.vanish(owner),
+            _ => {}
+        }
+    }
+
+    #[export]
+    fn vanish(&mut self, owner: &Area) {
+        if !self.flying {
+            return;
+        }
+        unsafe {
+            owner.call_deferred("cartridge_fallen", &[Variant::from_u64(self.generation)]);
+        }
+        self.flying = false;
+    }
+
+    #[export]
+    fn cartridge_fallen(&self, owner: &Area, generation: u64) {
+        // recycled and fired again since it fell
+        if generation != self.generation {
+            return;
+        }
+        // a second fall of the same shot finds it collected, the magazine ignores the repeat
+        if let Some(parent) = owner.get_parent() {
+            unsafe {
+                parent.assume_safe().remove_child(owner.assume_shared());
+            }
+        }
+        let area = unsafe { owner.assume_unique() };
+        owner.emit_signal(
+            "collect",
+            &[Variant::from_object(area), Variant::from_u64(generation)],
+        );
+    }
+}
+
+impl Cartridge for Bullet {
+    fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    fn unload(&mut self, _owner: TRef<Area>) {
+        self.flying = false;
+        self.shooter = None;
+    }
+}
@@ -0,0 +1,163 @@
+use gdnative::api::{MultiMesh, MultiMeshInstance, SpatialMaterial, SphereMesh};
+use gdnative::prelude::*;
+
+use super::play_field::PlayField;
+use super::{advance_xz, AlienEnv, ALIEN_BULLET_GROUP};
+use crate::autopilot::Threat;
+use crate::collision::Circle;
+use crate::events::BulletOrigin;
+
+struct FieldBullet {
+    pos: Vector3,
+    velocity: Vector3,
+    origin: BulletOrigin,
+}
+
+/// Alien bullets kept in a `Vec` and drawn through one `MultiMesh`, without a node per bullet.
+/// Hits are tested by the Stage, the bullets have no Area.
+#[derive(NativeClass)]
+#[inherit(MultiMeshInstance)]
+pub struct BulletField {
+    #[property(default = 4000)]
+    pub capacity: i32,
+    #[property(default = 0.25)]
+    pub radius: f32,
+    #[property]
+    color: Color,
+
+    bullets: Vec<FieldBullet>,
+    alien_env: AlienEnv,
+}
+
+#[gdnative::methods]
+impl BulletField {
+    fn new(_owner: &MultiMeshInstance) -> Self {
+        BulletField {
+            capacity: 4000,
+            radius: 0.25,
+            color: Color::rgb(0.0431373, 0.8, 1.0),
+
+            bullets: Vec::new(),
+            alien_env: AlienEnv::new(),
+        }
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &MultiMeshInstance) {
+        owner.add_to_group(ALIEN_BULLET_GROUP, false);
+
+        if owner.multimesh().is_none() {
+            let material = SpatialMaterial::new();
+            material.set_albedo(Color::rgb(0.0, 0.0, 0.0));
+            material.set_feature(SpatialMaterial::FEATURE_EMISSION, true);
+            material.set_emission(self.color);
+
+            let mesh = SphereMesh::new();
+            mesh.set_radius(self.radius);
+            mesh.set_height(self.radius * 2.0);
+            mesh.set_radial_segments(8);
+            mesh.set_rings(4);
+            mesh.set_material(material);
+
+            let multimesh = MultiMesh::new();
+            multimesh.set_mesh(mesh);
+            owner.set_multimesh(multimesh);
+        }
+        if let Some(multimesh) = owner.multimesh() {
+            let multimesh = unsafe { multimesh.assume_safe() };
+            multimesh.set_transform_format(MultiMesh::TRANSFORM_3D);
+            multimesh.set_instance_count(self.capacity as i64);
+            multimesh.set_visible_instance_count(0);
+        }
+    }
+
+    pub fn set_play_field(&mut self, field: &PlayField) {
+        self.alien_env.init(field);
+    }
+
+    /// Fires a bullet. Returns `false` when the field is full.
+    pub fn push(&mut self, pos: Vector3, dir: Vector3, speed: f32, origin: BulletOrigin) -> bool {
+        if self.bullets.len() >= self.capacity as usize {
+            return false;
+        }
+        let mut dir = dir.normalize();
+        if !dir.is_finite() {
+            dir = Vector3::new(0.0, 0.0, 1.0);
+        }
+        self.bullets.push(FieldBullet {
+            pos,
+            velocity: dir * speed,
+            origin,
+        });
+        true
+    }
+
+    pub fn origin(&self, i: usize) -> Option<&BulletOrigin> {
+        self.bullets.get(i).map(|b| &b.origin)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bullets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bullets.is_empty()
+    }
+
+    pub fn circles(&self) -> Vec<Circle> {
+        self.bullets
+            .iter()
+            .map(|b| Circle::new(b.pos.x, b.pos.z, self.radius))
+            .collect()
+    }
+
+    pub fn threats(&self) -> Vec<Threat> {
+        self.bullets
+            .iter()
+            .map(|b| Threat::new(b.pos.x, b.pos.z, b.velocity.x, b.velocity.z, self.radius))
+            .collect()
+    }
+
+    /// Removes the bullets at `indexes`, as returned by `collision::collide`.
+    pub fn remove(&mut self, indexes: &mut Vec<usize>) {
+        indexes.sort_unstable();
+        indexes.dedup();
+        for &i in indexes.iter().rev() {
+            self.bullets.swap_remove(i);
+        }
+    }
+
+    #[export]
+    fn vanish(&mut self, owner: &MultiMeshInstance) {
+        self.bullets.clear();
+        self.sync(owner);
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &MultiMeshInstance, delta: f64) {
+        let delta = delta as f32;
+        for b in self.bullets.iter_mut() {
+            b.pos = advance_xz(b.pos, b.velocity, delta);
+        }
+        let alien_env = &self.alien_env;
+        self.bullets.retain(|b| !alien_env.out_of_field(b.pos));
+        self.sync(owner);
+    }
+
+    fn sync(&self, owner: &MultiMeshInstance) {
+        let multimesh = match owner.multimesh() {
+            Some(multimesh) => unsafe { multimesh.assume_safe() },
+            None => return,
+        };
+        for (i, b) in self.bullets.iter().enumerate() {
+            multimesh.set_instance_transform(
+                i as i64,
+                Transform {
+                    basis: Basis::identity(),
+                    origin: b.pos,
+                },
+            );
+        }
+        multimesh.set_visible_instance_count(self.bullets.len() as i64);
+    }
+}
@@ -0,0 +1,305 @@
+use gdnative::api::{ConfigFile, InputEvent, InputEventJoypadButton, InputEventKey, InputMap, OS};
+use gdnative::prelude::*;
+
+pub const PAUSE: &str = "pause";
+
+/// Action names of one player.
+pub struct PlayerActions {
+    pub move_left: &'static str,
+    pub move_right: &'static str,
+    pub move_up: &'static str,
+    pub move_down: &'static str,
+    pub shoot: &'static str,
+    pub focus: &'static str,
+    pub bomb: &'static str,
+    /// switches the option pods to the next formation
+    pub formation: &'static str,
+}
+
+pub const PLAYER_ACTIONS: [PlayerActions; 2] = [
+    PlayerActions {
+        move_left: "move_left",
+        move_right: "move_right",
+        move_up: "move_up",
+        move_down: "move_down",
+        shoot: "shoot",
+        focus: "focus",
+        bomb: "bomb",
+        formation: "formation",
+    },
+    PlayerActions {
+        move_left: "p2_move_left",
+        move_right: "p2_move_right",
+        move_up: "p2_move_up",
+        move_down: "p2_move_down",
+        shoot: "p2_shoot",
+        focus: "p2_focus",
+        bomb: "p2_bomb",
+        formation: "p2_formation",
+    },
+];
+
+pub fn player_actions(player_index: usize) -> &'static PlayerActions {
+    &PLAYER_ACTIONS[player_index.min(PLAYER_ACTIONS.len() - 1)]
+}
+
+const CONFIG_PATH: &str = "user://input.cfg";
+const KEY_SECTION: &str = "key";
+const JOYPAD_SECTION: &str = "joypad";
+const DEVICE_SECTION: &str = "joypad_device";
+const DEADZONE: f64 = 0.5;
+
+// Godot 3 scancodes
+const KEY_SPACE: i64 = 32;
+const KEY_A: i64 = 65;
+const KEY_C: i64 = 67;
+const KEY_D: i64 = 68;
+const KEY_I: i64 = 73;
+const KEY_J: i64 = 74;
+const KEY_K: i64 = 75;
+const KEY_L: i64 = 76;
+const KEY_O: i64 = 79;
+const KEY_P: i64 = 80;
+const KEY_S: i64 = 83;
+const KEY_U: i64 = 85;
+const KEY_W: i64 = 87;
+const KEY_X: i64 = 88;
+const KEY_Y: i64 = 89;
+const KEY_ESCAPE: i64 = 16777217;
+const KEY_ENTER: i64 = 16777221;
+const KEY_LEFT: i64 = 16777231;
+const KEY_UP: i64 = 16777232;
+const KEY_RIGHT: i64 = 16777233;
+const KEY_DOWN: i64 = 16777234;
+const KEY_SHIFT: i64 = 16777237;
+
+// Godot 3 joypad button indexes
+const JOY_A: i64 = 0;
+const JOY_B: i64 = 1;
+const JOY_Y: i64 = 3;
+const JOY_R: i64 = 5;
+const JOY_START: i64 = 11;
+const JOY_DPAD_UP: i64 = 12;
+const JOY_DPAD_DOWN: i64 = 13;
+const JOY_DPAD_LEFT: i64 = 14;
+const JOY_DPAD_RIGHT: i64 = 15;
+
+#[derive(Clone)]
+pub struct Binding {
+    pub action: &'static str,
+    pub keys: Vec<i64>,
+    pub joy_buttons: Vec<i64>,
+    /// joypad device the buttons are read from
+    pub device: i64,
+}
+
+impl Binding {
+    fn new(action: &'static str, keys: &[i64], joy_buttons: &[i64], device: i64) -> Self {
+        Binding {
+            action,
+            keys: keys.to_vec(),
+            joy_buttons: joy_buttons.to_vec(),
+            device,
+        }
+    }
+}
+
+pub fn default_bindings() -> Vec<Binding> {
+    let p1 = player_actions(0);
+    let p2 = player_actions(1);
+    vec![
+        Binding::new(p1.move_left, &[KEY_LEFT, KEY_A], &[JOY_DPAD_LEFT], 0),
+        Binding::new(p1.move_right, &[KEY_RIGHT, KEY_D], &[JOY_DPAD_RIGHT], 0),
+        Binding::new(p1.move_up, &[KEY_UP, KEY_W], &[JOY_DPAD_UP], 0),
+        Binding::new(p1.move_down, &[KEY_DOWN, KEY_S], &[JOY_DPAD_DOWN], 0),
+        Binding::new(p1.shoot, &[KEY_SPACE], &[JOY_A], 0),
+        Binding::new(p1.focus, &[KEY_SHIFT], &[JOY_R], 0),
+        Binding::new(p1.bomb, &[KEY_X], &[JOY_B], 0),
+        Binding::new(p1.formation, &[KEY_C], &[JOY_Y], 0),
+        Binding::new(p2.move_left, &[KEY_J], &[JOY_DPAD_LEFT], 1),
+        Binding::new(p2.move_right, &[KEY_L], &[JOY_DPAD_RIGHT], 1),
+        Binding::new(p2.move_up, &[KEY_I], &[JOY_DPAD_UP], 1),
+        Binding::new(p2.move_down, &[KEY_K], &[JOY_DPAD_DOWN], 1),
+        Binding::new(p2.shoot, &[KEY_ENTER], &[JOY_A], 1),
+        Binding::new(p2.focus, &[KEY_O], &[JOY_R], 1),
+        Binding::new(p2.bomb, &[KEY_U], &[JOY_B], 1),
+        Binding::new(p2.formation, &[KEY_Y], &[JOY_Y], 1),
+        Binding::new(PAUSE, &[KEY_ESCAPE, KEY_P], &[JOY_START], 0),
+    ]
+}
+
+fn to_int_vec(var: &Variant) -> Option<Vec<i64>> {
+    let arr = var.try_to_array()?;
+    Some(arr.iter().map(|v| v.to_i64()).collect())
+}
+
+fn to_variant_array(vals: &[i64]) -> VariantArray {
+    let arr = VariantArray::new();
+    for v in vals {
+        arr.push(Variant::from_i64(*v));
+    }
+    arr.into_shared()
+}
+
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(Self::register_signals)]
+pub struct InputConfig {
+    bindings: Vec<Binding>,
+}
+
+#[gdnative::methods]
+impl InputConfig {
+    fn new(_owner: &Node) -> Self {
+        InputConfig {
+            bindings: default_bindings(),
+        }
+    }
+
+    fn register_signals(builder: &ClassBuilder<Self>) {
+        builder.add_signal(Signal {
+            name: "bindings_changed",
+            args: &[],
+        });
+    }
+
+    #[export]
+    fn _ready(&mut self, _owner: &Node) {
+        self.load_bindings();
+        self.apply_bindings();
+    }
+
+    fn binding(&self, action: &str) -> Option<&Binding> {
+        self.bindings.iter().find(|b| b.action == action)
+    }
+
+    fn binding_mut(&mut self, action: &str) -> Option<&mut Binding> {
+        self.bindings.iter_mut().find(|b| b.action == action)
+    }
+
+    fn load_bindings(&mut self) {
+        let config = ConfigFile::new();
+        if config.load(CONFIG_PATH).is_err() {
+            // no saved bindings yet, keep defaults
+            return;
+        }
+        for b in self.bindings.iter_mut() {
+            if config.has_section_key(KEY_SECTION, b.action) {
+                if let Some(keys) =
+                    to_int_vec(&config.get_value(KEY_SECTION, b.action, Variant::new()))
+                {
+                    b.keys = keys;
+                }
+            }
+            if config.has_section_key(JOYPAD_SECTION, b.action) {
+                if let Some(buttons) =
+                    to_int_vec(&config.get_value(JOYPAD_SECTION, b.action, Variant::new()))
+                {
+                    b.joy_buttons = buttons;
+                }
+            }
+            if config.has_section_key(DEVICE_SECTION, b.action) {
+                b.device = config
+                    .get_value(DEVICE_SECTION, b.action, Variant::new())
+                    .to_i64();
+            }
+        }
+    }
+
+    fn apply_bindings(&self) {
+        let input_map = InputMap::godot_singleton();
+        for b in self.bindings.iter() {
+            if !input_map.has_action(b.action) {
+                input_map.add_action(b.action, DEADZONE);
+            }
+            input_map.action_erase_events(b.action);
+            for key in b.keys.iter() {
+                let ev = InputEventKey::new();
+                ev.set_scancode(*key);
+                input_map.action_add_event(b.action, ev.upcast::<InputEvent>());
+            }
+            for button in b.joy_buttons.iter() {
+                let ev = InputEventJoypadButton::new();
+                ev.set_device(b.device);
+                ev.set_button_index(*button);
+                input_map.action_add_event(b.action, ev.upcast::<InputEvent>());
+            }
+        }
+    }
+
+    #[export]
+    fn save_bindings(&self, _owner: &Node) -> bool {
+        let config = ConfigFile::new();
+        for b in self.bindings.iter() {
+            config.set_value(KEY_SECTION, b.action, to_variant_array(&b.keys));
+            config.set_value(JOYPAD_SECTION, b.action, to_variant_array(&b.joy_buttons));
+            config.set_value(DEVICE_SECTION, b.action, b.device);
+        }
+        match config.save(CONFIG_PATH) {
+            Ok(_) => true,
+            Err(e) => {
+                godot_warn!("failed to save input config: {:?}", e);
+                false
+            }
+        }
+    }
+
+    #[export]
+    fn get_actions(&self, _owner: &Node) -> Vec<String> {
+        self.bindings.iter().map(|b| b.action.to_string()).collect()
+    }
+
+    #[export]
+    fn get_binding_names(&self, _owner: &Node, action: String) -> Vec<String> {
+        let b = match self.binding(&action) {
+            Some(b) => b,
+            None => return vec![],
+        };
+        let os = OS::godot_singleton();
+        b.keys
+            .iter()
+            .map(|k| os.get_scancode_string(*k).to_string())
+            .chain(b.joy_buttons.iter().map(|j| format!("Joy {}", j)))
+            .collect()
+    }
+
+    /// Binds the key or joypad button of `event` to `action`,
+    /// replacing the previous bindings of the same device and unbinding it from other actions.
+    #[export]
+    fn rebind(&mut self, owner: &Node, action: String, event: Ref<InputEvent>) -> bool {
+        if self.binding(&action).is_none() {
+            return false;
+        }
+        let event = unsafe { event.assume_safe() };
+
+        if let Some(key) = event.cast::<InputEventKey>() {
+            let code = key.scancode();
+            for b in self.bindings.iter_mut() {
+                b.keys.retain(|k| *k != code);
+            }
+            self.binding_mut(&action).unwrap().keys = vec![code];
+        } else if let Some(button) = event.cast::<InputEventJoypadButton>() {
+            let index = button.button_index();
+            let device = button.device();
+            for b in self.bindings.iter_mut().filter(|b| b.device == device) {
+                b.joy_buttons.retain(|j| *j != index);
+            }
+            let b = self.binding_mut(&action).unwrap();
+            b.joy_buttons = vec![index];
+            b.device = device;
+        } else {
+            return false;
+        }
+
+        self.apply_bindings();
+        owner.emit_signal("bindings_changed", &[]);
+        true
+    }
+
+    #[export]
+    fn reset_to_default(&mut self, owner: &Node) {
+        self.bindings = default_bindings();
+        self.apply_bindings();
+        owner.emit_signal("bindings_changed", &[]);
+    }
+}
@@ -0,0 +1,79 @@
+use gdnative::prelude::*;
+use std::ops::BitOr;
+
+/// Collision layer bits, the same as the layer names in project.godot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layer(i64);
+
+impl Layer {
+    pub const NONE: Layer = Layer(0);
+    pub const PLAYER: Layer = Layer(1);
+    pub const ALIEN: Layer = Layer(2);
+    pub const BULLET: Layer = Layer(4);
+    pub const ALIEN_BULLET: Layer = Layer(8);
+
+    pub fn bits(self) -> i64 {
+        self.0
+    }
+
+    pub fn from_bits(bits: i64) -> Self {
+        Layer(bits)
+    }
+}
+
+impl BitOr for Layer {
+    type Output = Layer;
+
+    fn bitor(self, rhs: Layer) -> Layer {
+        Layer(self.0 | rhs.0)
+    }
+}
+
+/// What an area that entered a hit handler is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitKind {
+    Player,
+    Alien,
+    PlayerBullet,
+    AlienBullet,
+    /// areas on no layer only monitor, like the BulletWall
+    Wall,
+    Unknown,
+}
+
+pub fn classify_hit(area: &Area) -> HitKind {
+    match Layer::from_bits(area.collision_layer()) {
+        Layer::NONE => HitKind::Wall,
+        Layer::PLAYER => HitKind::Player,
+        Layer::ALIEN => HitKind::Alien,
+        Layer::BULLET => HitKind::PlayerBullet,
+        Layer::ALIEN_BULLET => HitKind::AlienBullet,
+        _ => HitKind::Unknown,
+    }
+}
+
+/// `classify_hit` for the area argument of an `area_entered` handler.
+pub fn classify_hit_variant(area: &Variant) -> HitKind {
+    match area.try_to_object::<Area>() {
+        Some(area) => classify_hit(&unsafe { area.assume_safe() }),
+        None => HitKind::Unknown,
+    }
+}
+
+/// Reports an error when the scene sets other layer or mask bits than the code expects.
+pub fn validate_layers(area: &Area, layer: Layer, mask: Layer) -> bool {
+    let actual_layer = Layer::from_bits(area.collision_layer());
+    let actual_mask = Layer::from_bits(area.collision_mask());
+    if actual_layer == layer && actual_mask == mask {
+        return true;
+    }
+    godot_error!(
+        "{}: collision layer {} / mask {}, expected {} / {}",
+        area.name(),
+        actual_layer.bits(),
+        actual_mask.bits(),
+        layer.bits(),
+        mask.bits()
+    );
+    false
+}
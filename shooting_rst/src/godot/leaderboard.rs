@@ -0,0 +1,311 @@
+use gdnative::api::{HTTPClient, HTTPRequest};
+use gdnative::prelude::*;
+use std::collections::VecDeque;
+
+use crate::leaderboard::{
+    around_path, parse_error, parse_standings, top_path, Receipt, Standing, Submission, SUBMIT_PATH,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum RequestKind {
+    Submit,
+    Top,
+    Around,
+}
+
+impl RequestKind {
+    fn name(self) -> &'static str {
+        match self {
+            RequestKind::Submit => "submit",
+            RequestKind::Top => "top",
+            RequestKind::Around => "around",
+        }
+    }
+}
+
+struct PendingRequest {
+    kind: RequestKind,
+    path: String,
+    // POSTed when there is one
+    body: Option<String>,
+}
+
+/// Talks to the online leaderboard through `HTTPRequest`, so the wasm build reaches it too.
+/// One request is in flight at a time, the rest wait in order.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(Self::register_signals)]
+pub struct Leaderboard {
+    // http://host:port, the paths of crate::leaderboard are appended
+    #[property]
+    pub base_url: String,
+    #[property(default = 10)]
+    pub top_limit: i32,
+    #[property(default = 5)]
+    pub neighbour_span: i32,
+
+    http: Option<Ref<HTTPRequest, Shared>>,
+    pending: VecDeque<PendingRequest>,
+    in_flight: Option<RequestKind>,
+}
+
+#[gdnative::methods]
+impl Leaderboard {
+    fn new(_owner: &Node) -> Self {
+        Leaderboard {
+            base_url: String::new(),
+            top_limit: 10,
+            neighbour_span: 5,
+
+            http: None,
+            pending: VecDeque::new(),
+            in_flight: None,
+        }
+    }
+
+    fn register_signals(builder: &ClassBuilder<Self>) {
+        builder.add_signal(Signal {
+            name: "submitted",
+            args: &[
+                SignalArgument {
+                    name: "id",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "rank",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "standings_received",
+            args: &[
+                SignalArgument {
+                    name: "kind",
+                    default: Variant::from_str("top"),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "standings",
+                    default: Variant::from_array(&VariantArray::new_shared()),
+                    export_info: ExportInfo::new(VariantType::VariantArray),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "request_failed",
+            args: &[
+                SignalArgument {
+                    name: "kind",
+                    default: Variant::from_str("top"),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "message",
+                    default: Variant::from_str(""),
+                    export_info: ExportInfo::new(VariantType::GodotString),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Node) {
+        // the game over screen may pause the tree
+        owner.set_pause_mode(Node::PAUSE_MODE_PROCESS);
+        let http = HTTPRequest::new();
+        http.connect(
+            "request_completed",
+            unsafe { owner.assume_shared() },
+            "_on_request_completed",
+            VariantArray::new_shared(),
+            0,
+        )
+        .unwrap();
+        let http = http.into_shared();
+        owner.add_child(http.clone(), false);
+        self.http = Some(http);
+    }
+
+    /// Queues a submission, checked here so a bad one never leaves the game.
+    pub fn submit(&mut self, owner: &Node, submission: &Submission) {
+        if let Err(e) = submission.validate() {
+            self.fail(owner, RequestKind::Submit, &e.to_string());
+            return;
+        }
+        self.enqueue(
+            owner,
+            PendingRequest {
+                kind: RequestKind::Submit,
+                path: SUBMIT_PATH.to_string(),
+                body: Some(submission.to_json()),
+            },
+        );
+    }
+
+    #[export]
+    pub fn fetch_top(&mut self, owner: &Node) {
+        self.enqueue(
+            owner,
+            PendingRequest {
+                kind: RequestKind::Top,
+                path: top_path(self.top_limit.max(1) as u32),
+                body: None,
+            },
+        );
+    }
+
+    /// The standings around entry `id`, as answered by a submission.
+    #[export]
+    fn fetch_around(&mut self, owner: &Node, id: i64) {
+        if id < 0 {
+            self.fail(owner, RequestKind::Around, "no such entry");
+            return;
+        }
+        self.enqueue(
+            owner,
+            PendingRequest {
+                kind: RequestKind::Around,
+                path: around_path(id as u64, self.neighbour_span.max(0) as u32),
+                body: None,
+            },
+        );
+    }
+
+    fn enqueue(&mut self, owner: &Node, request: PendingRequest) {
+        if self.base_url.is_empty() {
+            self.fail(owner, request.kind, "no leaderboard configured");
+            return;
+        }
+        self.pending.push_back(request);
+        self.send_next(owner);
+    }
+
+    fn send_next(&mut self, owner: &Node) {
+        if self.in_flight.is_some() {
+            return;
+        }
+        let http = match self.http.as_ref() {
+            Some(http) => unsafe { http.assume_safe() },
+            None => return,
+        };
+        while let Some(request) = self.pending.pop_front() {
+            let url = format!("{}{}", self.base_url.trim_end_matches('/'), request.path);
+            let (method, body) = match request.body.as_deref() {
+                Some(body) => (HTTPClient::METHOD_POST, body),
+                None => (HTTPClient::METHOD_GET, ""),
+            };
+            let mut headers = StringArray::new();
+            headers.push("Content-Type: application/json".into());
+            match http.request(url, headers, true, method, body) {
+                Ok(()) => {
+                    self.in_flight = Some(request.kind);
+                    return;
+                }
+                Err(e) => self.fail(owner, request.kind, &format!("{:?}", e)),
+            }
+        }
+    }
+
+    #[export]
+    fn _on_request_completed(
+        &mut self,
+        owner: &Node,
+        result: i64,
+        code: i64,
+        _headers: StringArray,
+        body: ByteArray,
+    ) {
+        let kind = match self.in_flight.take() {
+            Some(kind) => kind,
+            None => return,
+        };
+        let body = String::from_utf8_lossy(&body.read()).into_owned();
+        if result != HTTPRequest::RESULT_SUCCESS {
+            self.fail(
+                owner,
+                kind,
+                &format!("request failed with result {}", result),
+            );
+        } else if !(200..300).contains(&code) {
+            let message = parse_error(&body).unwrap_or_else(|| format!("status {}", code));
+            self.fail(owner, kind, &message);
+        } else {
+            self.received(owner, kind, &body);
+        }
+        self.send_next(owner);
+    }
+
+    fn received(&self, owner: &Node, kind: RequestKind, body: &str) {
+        if kind == RequestKind::Submit {
+            match Receipt::parse(body) {
+                Ok(receipt) => emit_deferred(
+                    owner,
+                    "submitted",
+                    &[
+                        Variant::from_u64(receipt.id),
+                        // 0 while the entry is held, off the standings
+                        Variant::from_u64(receipt.rank.unwrap_or(0) as u64),
+                    ],
+                ),
+                Err(e) => self.fail(owner, kind, &e.to_string()),
+            }
+            return;
+        }
+        match parse_standings(body) {
+            Ok(standings) => {
+                let list = VariantArray::new();
+                for standing in standings.iter() {
+                    list.push(standing_dictionary(standing));
+                }
+                emit_deferred(
+                    owner,
+                    "standings_received",
+                    &[
+                        Variant::from_str(kind.name()),
+                        Variant::from_array(&list.into_shared()),
+                    ],
+                );
+            }
+            Err(e) => self.fail(owner, kind, &e.to_string()),
+        }
+    }
+
+    fn fail(&self, owner: &Node, kind: RequestKind, message: &str) {
+        godot_warn!("leaderboard {}: {}", kind.name(), message);
+        emit_deferred(
+            owner,
+            "request_failed",
+            &[Variant::from_str(kind.name()), Variant::from_str(message)],
+        );
+    }
+}
+
+// the handlers may call back into the Leaderboard, which is still borrowed while it emits
+fn emit_deferred(owner: &Node, signal: &str, args: &[Variant]) {
+    let mut call_args = vec![Variant::from_str(signal)];
+    call_args.extend_from_slice(args);
+    unsafe {
+        owner.call_deferred("emit_signal", &call_args);
+    }
+}
+
+// seeds and hashes stay strings, a Variant int would turn large ones negative
+fn standing_dictionary(standing: &Standing) -> Dictionary {
+    let dict = Dictionary::new();
+    dict.insert("rank", standing.rank as i64);
+    dict.insert("id", standing.id as i64);
+    dict.insert("name", standing.name.as_str());
+    dict.insert("score", standing.score as i64);
+    dict.insert("seed", standing.seed.to_string());
+    dict.insert("replay_hash", format!("{:016x}", standing.replay_hash));
+    dict.into_shared()
+}
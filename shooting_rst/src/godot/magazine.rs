@@ -0,0 +1,269 @@
+use gdnative::api::OS;
+use gdnative::nativescript::user_data::MapMut;
+use gdnative::prelude::*;
+use std::collections::VecDeque;
+
+use super::instance_scene;
+
+/// A class a `Magazine` hands out.
+///
+/// The magazine numbers every hammer with a generation, the object sends it back with its
+/// collect signal so a late collect of an object that was recycled meanwhile is ignored.
+pub trait Cartridge: NativeClass {
+    fn set_generation(&mut self, generation: u64);
+    /// Puts the object back to its state in the magazine, called when it is taken back while out.
+    fn unload(&mut self, owner: TRef<Self::Base>);
+}
+
+/// What a magazine does when it runs out of objects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolPolicy {
+    /// Keep the initial number of objects, `hammer` returns `None` when empty.
+    Fixed,
+    /// Instance `step` more objects when empty, up to `cap` in total.
+    Grow { step: usize, cap: usize },
+    /// Take back the object that has been out for the longest time.
+    RecycleOldest,
+}
+
+impl PoolPolicy {
+    /// Builds a policy from exported properties: 0 = fixed, 1 = grow, 2 = recycle oldest.
+    pub fn from_property(kind: i32, step: i32, cap: i32) -> Self {
+        match kind {
+            1 => PoolPolicy::Grow {
+                step: step.max(1) as usize,
+                cap: cap.max(0) as usize,
+            },
+            2 => PoolPolicy::RecycleOldest,
+            _ => PoolPolicy::Fixed,
+        }
+    }
+}
+
+/// Counters of one magazine, for debugging pool sizes and leaks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PoolStats {
+    pub capacity: usize,
+    pub in_flight: usize,
+    pub high_water: usize,
+    pub hammers: u64,
+    pub returns: u64,
+    pub recycled: u64,
+    pub exhausted: u64,
+    /// objects flagged by `check_leaks` that are still out
+    pub overdue: usize,
+}
+
+impl PoolStats {
+    pub fn to_dictionary(&self) -> Dictionary {
+        let dict = Dictionary::new();
+        dict.insert("capacity", self.capacity as i64);
+        dict.insert("in_flight", self.in_flight as i64);
+        dict.insert("high_water", self.high_water as i64);
+        dict.insert("hammers", self.hammers as i64);
+        dict.insert("returns", self.returns as i64);
+        dict.insert("recycled", self.recycled as i64);
+        dict.insert("exhausted", self.exhausted as i64);
+        dict.insert("overdue", self.overdue as i64);
+        dict.into_shared()
+    }
+}
+
+struct OutObject<T: NativeClass> {
+    object: Instance<T, Shared>,
+    // msec when hammered
+    since: i64,
+    reported: bool,
+    generation: u64,
+}
+
+fn now_msec() -> i64 {
+    OS::godot_singleton().get_ticks_msec()
+}
+
+pub struct Magazine<T>
+where
+    T: Cartridge,
+    T::UserData: MapMut,
+    <T as NativeClass>::Base:
+        gdnative::object::GodotObject<RefKind = ManuallyManaged> + SubClass<Node>,
+{
+    // detached from the tree while in the magazine
+    bullets: VecDeque<Instance<T, Shared>>,
+    // objects out of the magazine, oldest first
+    in_flight: VecDeque<OutObject<T>>,
+    capacity: usize,
+    scene: Ref<PackedScene, Shared>,
+    policy: PoolPolicy,
+    stats: PoolStats,
+    // generation of the last hammer
+    generation: u64,
+}
+
+impl<T> Magazine<T>
+where
+    T: Cartridge,
+    T::UserData: MapMut,
+    <T as NativeClass>::Base:
+        gdnative::object::GodotObject<RefKind = ManuallyManaged> + SubClass<Node>,
+{
+    pub fn new(bullet_scene: &Ref<PackedScene, Shared>, bullet_num: usize) -> Self {
+        Self::with_policy(bullet_scene, bullet_num, PoolPolicy::Fixed)
+    }
+
+    pub fn with_policy(
+        bullet_scene: &Ref<PackedScene, Shared>,
+        bullet_num: usize,
+        policy: PoolPolicy,
+    ) -> Self {
+        let mut magazine = Magazine {
+            bullets: VecDeque::new(),
+            in_flight: VecDeque::new(),
+            capacity: 0,
+            scene: bullet_scene.clone(),
+            policy,
+            stats: PoolStats::default(),
+            generation: 0,
+        };
+        magazine.grow(bullet_num);
+        magazine
+    }
+
+    fn grow(&mut self, num: usize) {
+        let scene = &self.scene;
+        let bullets: Vec<_> = (0..num)
+            .filter_map(|_| {
+                let r = instance_scene(scene)?;
+                Instance::from_base(r).map(Instance::into_shared)
+            })
+            .collect();
+        self.capacity += bullets.len();
+        self.bullets.extend(bullets);
+    }
+
+    fn recycle_oldest(&mut self) -> Option<Instance<T, Shared>> {
+        let oldest = self.in_flight.pop_front()?.object;
+        self.stats.recycled += 1;
+        unsafe { oldest.assume_safe() }
+            .map_mut(|t, owner| t.unload(owner))
+            .ok();
+        let node = unsafe { oldest.base().assume_safe() }.upcast::<Node>();
+        if let Some(parent) = node.get_parent() {
+            unsafe { parent.assume_safe() }.remove_child(node);
+        }
+        Some(oldest)
+    }
+
+    /// Takes an object back, `generation` is the one it was hammered with.
+    pub fn charge_bullet(&mut self, bullet_base: Ref<T::Base, Shared>, generation: u64) {
+        let ptr = bullet_base.as_ptr();
+        let i = match self
+            .in_flight
+            .iter()
+            .position(|b| b.object.base().as_ptr() == ptr && b.generation == generation)
+        {
+            Some(i) => i,
+            // not out of this magazine, e.g. charged twice or recycled since
+            None => return,
+        };
+        if let Some(out) = self.in_flight.remove(i) {
+            self.stats.returns += 1;
+            self.bullets.push_back(out.object);
+        }
+    }
+
+    pub fn get_left_num(&self) -> usize {
+        self.bullets.len()
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn get_in_flight_num(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            capacity: self.capacity,
+            in_flight: self.in_flight.len(),
+            overdue: self.in_flight.iter().filter(|o| o.reported).count(),
+            ..self.stats
+        }
+    }
+
+    /// Flags objects out longer than `threshold_msec`. Returns the number of newly flagged ones.
+    pub fn check_leaks(&mut self, threshold_msec: i64) -> usize {
+        let now = now_msec();
+        let mut flagged = 0;
+        for out in self.in_flight.iter_mut() {
+            if !out.reported && now - out.since > threshold_msec {
+                out.reported = true;
+                flagged += 1;
+            }
+        }
+        flagged
+    }
+
+    /// Every object has come back to the magazine.
+    pub fn is_full(&self) -> bool {
+        self.bullets.len() >= self.capacity
+    }
+
+    /// Takes an object out following the pool policy. `None` means the pool is exhausted.
+    /// The magazine keeps a reference to it until it is charged back or recycled.
+    pub fn hammer(&mut self) -> Option<Instance<T, Shared>> {
+        if self.bullets.is_empty() {
+            match self.policy {
+                PoolPolicy::Fixed => {}
+                PoolPolicy::Grow { step, cap } => {
+                    self.grow(step.min(cap.saturating_sub(self.capacity)));
+                }
+                PoolPolicy::RecycleOldest => {
+                    if let Some(bullet) = self.recycle_oldest() {
+                        self.bullets.push_back(bullet);
+                    }
+                }
+            }
+        }
+
+        let bullet = match self.bullets.pop_front() {
+            Some(bullet) => bullet,
+            None => {
+                self.stats.exhausted += 1;
+                return None;
+            }
+        };
+        self.stats.hammers += 1;
+        self.generation += 1;
+        let generation = self.generation;
+        unsafe { bullet.assume_safe() }
+            .map_mut(|t, _| t.set_generation(generation))
+            .ok();
+        self.in_flight.push_back(OutObject {
+            object: bullet.clone(),
+            since: now_msec(),
+            reported: false,
+            generation,
+        });
+        self.stats.high_water = self.stats.high_water.max(self.in_flight.len());
+        Some(bullet)
+    }
+}
+
+impl<T> Drop for Magazine<T>
+where
+    T: Cartridge,
+    T::UserData: MapMut,
+    <T as NativeClass>::Base:
+        gdnative::object::GodotObject<RefKind = ManuallyManaged> + SubClass<Node>,
+{
+    fn drop(&mut self) {
+        while let Some(bullet) = self.bullets.pop_front() {
+            unsafe { bullet.base().assume_safe() }
+                .upcast::<Node>()
+                .queue_free();
+        }
+    }
+}
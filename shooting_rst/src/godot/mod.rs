@@ -0,0 +1,180 @@
+use gdnative::api::Area;
+use gdnative::prelude::*;
+
+mod alien;
+mod alien_bullet;
+mod bullet;
+mod bullet_field;
+mod input_config;
+mod layer;
+mod leaderboard;
+mod magazine;
+mod option_pod;
+mod play_field;
+mod player;
+mod scroll;
+mod stage;
+mod telemetry;
+use crate::barrage::{barrage_angles, rotate_xz, Shot};
+use alien::Alien;
+use alien_bullet::AlienBullet;
+use bullet::Bullet;
+use bullet_field::BulletField;
+use input_config::InputConfig;
+use leaderboard::Leaderboard;
+use option_pod::OptionPod;
+use play_field::PlayField;
+use player::Player;
+use stage::Stage;
+use telemetry::Telemetry;
+
+pub fn rotate_xz_vec3(v: Vector3, theta: f32) -> Vector3 {
+    let (x, z) = rotate_xz(v.x, v.z, theta);
+    Vector3::new(x, v.y, z)
+}
+
+/// Moves `pos` one frame along `velocity` on the xz plane, the motion the headless tools simulate.
+pub fn advance_xz(pos: Vector3, velocity: Vector3, delta: f32) -> Vector3 {
+    let mut shot = Shot {
+        x: pos.x,
+        z: pos.z,
+        vx: velocity.x,
+        vz: velocity.z,
+    };
+    shot.advance(delta);
+    Vector3::new(shot.x, pos.y, shot.z)
+}
+
+const ALIEN_BULLET_GROUP: &str = "alien_bullets";
+const PLAYER_GROUP: &str = "players";
+const PLAYER_BULLET_GROUP: &str = "player_bullets";
+const ALIEN_GROUP: &str = "aliens";
+
+/// Directions of the bullets of barrage `kind` aimed at `dir`.
+pub fn barrage_dirs(kind: usize, dir: Vector3) -> Vec<Vector3> {
+    barrage_angles(kind)
+        .into_iter()
+        .map(|theta| rotate_xz_vec3(dir, theta))
+        .collect()
+}
+
+pub struct Env {
+    time: f32,
+    theta: f32,
+    left_limit: f32,
+    right_limit: f32,
+    up_limit: f32,
+    down_limit: f32,
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Env {
+            time: 0.0,
+            theta: 0.0,
+            left_limit: 0.0,
+            right_limit: 0.0,
+            up_limit: 0.0,
+            down_limit: 0.0,
+        }
+    }
+
+    pub fn init(&mut self, field: &PlayField) {
+        self.left_limit = field.inner.left;
+        self.right_limit = field.inner.right;
+        self.up_limit = field.inner.up;
+        self.down_limit = field.inner.down;
+    }
+
+    pub fn clamp(&self, v: Vector3) -> Vector3 {
+        Vector3::new(
+            v.x.max(self.left_limit).min(self.right_limit),
+            v.y,
+            v.z.max(self.up_limit).min(self.down_limit),
+        )
+    }
+}
+
+pub struct AlienEnv {
+    left_limit: f32,
+    right_limit: f32,
+    up_limit: f32,
+    down_limit: f32,
+}
+
+impl AlienEnv {
+    pub fn new() -> Self {
+        AlienEnv {
+            left_limit: 0.0,
+            right_limit: 0.0,
+            up_limit: 0.0,
+            down_limit: 0.0,
+        }
+    }
+
+    pub fn init(&mut self, field: &PlayField) {
+        self.left_limit = field.outer.left;
+        self.right_limit = field.outer.right;
+        self.up_limit = field.outer.up;
+        self.down_limit = field.outer.down;
+    }
+
+    // global position, ground objects are children of the scrolling world
+    pub fn gone_far_away(&self, owner: &Spatial) -> bool {
+        self.out_of_field(owner.global_transform().origin)
+    }
+
+    pub fn out_of_field(&self, pos: Vector3) -> bool {
+        pos.x < self.left_limit
+            || pos.x > self.right_limit
+            || pos.z < self.up_limit
+            || pos.z > self.down_limit
+    }
+}
+
+fn areas_in_group(owner: &Node, group: &str) -> Vec<Ref<Area, Shared>> {
+    let tree = match owner.get_tree() {
+        Some(tree) => unsafe { tree.assume_safe() },
+        None => return vec![],
+    };
+    tree.get_nodes_in_group(group)
+        .iter()
+        .filter_map(|v| v.try_to_object::<Area>())
+        .collect()
+}
+
+// https://github.com/godot-rust/godot-rust/blob/master/examples/dodge_the_creeps/src/main_scene.rs
+fn instance_scene<Root>(scene: &Ref<PackedScene, Shared>) -> Option<Ref<Root, Unique>>
+where
+    Root: gdnative::object::GodotObject<RefKind = ManuallyManaged> + SubClass<Node>,
+{
+    let scene = unsafe { scene.assume_safe() };
+
+    let instance = scene.instance(PackedScene::GEN_EDIT_STATE_DISABLED)?;
+    // .expect("should be able to instance scene");
+
+    let instance = unsafe { instance.assume_unique() };
+
+    Some(instance.try_cast::<Root>().ok()?)
+    // .expect("root node type should be correct")
+}
+
+fn init(handle: InitHandle) {
+    // godot_print!("beep1");
+    handle.add_class::<Stage>();
+    // godot_print!("beep2");
+    handle.add_class::<Player>();
+    // godot_print!("beep3");
+    handle.add_class::<Bullet>();
+    // godot_print!("beep4");
+    handle.add_class::<Alien>();
+    // godot_print!("beep5");
+    handle.add_class::<AlienBullet>();
+    handle.add_class::<InputConfig>();
+    handle.add_class::<OptionPod>();
+    handle.add_class::<BulletField>();
+    handle.add_class::<Leaderboard>();
+    handle.add_class::<Telemetry>();
+}
+
+godot_init!(init);
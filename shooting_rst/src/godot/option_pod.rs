@@ -0,0 +1,56 @@
+use gdnative::prelude::*;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum OptionFormation {
+    Spread,
+    Snake,
+    Locked,
+}
+
+impl OptionFormation {
+    pub fn from_i64(v: i64) -> Self {
+        match v {
+            1 => OptionFormation::Snake,
+            2 => OptionFormation::Locked,
+            _ => OptionFormation::Spread,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            OptionFormation::Spread => OptionFormation::Snake,
+            OptionFormation::Snake => OptionFormation::Locked,
+            OptionFormation::Locked => OptionFormation::Spread,
+        }
+    }
+}
+
+#[derive(NativeClass)]
+#[inherit(Spatial)]
+pub struct OptionPod {
+    #[property(default = 12.0)]
+    follow_speed: f32,
+    pub target: Vector3,
+}
+
+#[gdnative::methods]
+impl OptionPod {
+    fn new(_owner: &Spatial) -> Self {
+        Self {
+            follow_speed: 12.0,
+            target: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Spatial) {
+        self.target = owner.translation();
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Spatial, delta: f64) {
+        let pos = owner.translation();
+        let t = (self.follow_speed * delta as f32).min(1.0);
+        owner.set_translation(pos + (self.target - pos) * t);
+    }
+}
@@ -0,0 +1,158 @@
+use gdnative::api::ConfigFile;
+use gdnative::prelude::*;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub left: f32,
+    pub right: f32,
+    pub up: f32,
+    pub down: f32,
+}
+
+/// Where things may be on the stage.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlayField {
+    /// players move inside, aliens turn at the sides
+    pub inner: Bounds,
+    /// aliens and bullets outside are despawned
+    pub outer: Bounds,
+    /// z of the rail aliens invade from
+    pub spawn_top_z: f32,
+    /// |x| of the side rails aliens cross from
+    pub spawn_side_x: f32,
+}
+
+impl Default for PlayField {
+    // the limits of stage.tscn
+    fn default() -> Self {
+        PlayField {
+            inner: Bounds {
+                left: -10.0,
+                right: 10.0,
+                up: -20.0,
+                down: 1.0,
+            },
+            outer: Bounds {
+                left: -25.0,
+                right: 25.0,
+                up: -30.0,
+                down: 6.0,
+            },
+            spawn_top_z: -27.0,
+            spawn_side_x: 22.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PlayFieldError {
+    MissingNodes(Vec<String>),
+    Load(String),
+    MissingKey {
+        path: String,
+        section: String,
+        key: String,
+    },
+}
+
+impl fmt::Display for PlayFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayFieldError::MissingNodes(names) => write!(
+                f,
+                "play field limit nodes are missing under the stage: {}",
+                names.join(", ")
+            ),
+            PlayFieldError::Load(path) => write!(f, "failed to load play field file {}", path),
+            PlayFieldError::MissingKey { path, section, key } => {
+                write!(f, "play field file {} has no [{}] {}", path, section, key)
+            }
+        }
+    }
+}
+
+const INNER_NODES: [&str; 4] = ["left_limit", "right_limit", "up_limit", "down_limit"];
+const OUTER_NODES: [&str; 4] = [
+    "alien_left_limit",
+    "alien_right_limit",
+    "alien_up_limit",
+    "alien_down_limit",
+];
+const BOUNDS_KEYS: [&str; 4] = ["left", "right", "up", "down"];
+
+fn bounds_from_nodes(stage: &Node, names: &[&str; 4], missing: &mut Vec<String>) -> Option<Bounds> {
+    let nodes: Vec<_> = names
+        .iter()
+        .filter_map(|name| {
+            let node = unsafe { stage.get_node_as::<Spatial>(*name) };
+            if node.is_none() {
+                missing.push(name.to_string());
+            }
+            node
+        })
+        .collect();
+    if nodes.len() < names.len() {
+        return None;
+    }
+    Some(Bounds {
+        left: nodes[0].translation().x,
+        right: nodes[1].translation().x,
+        up: nodes[2].translation().z,
+        down: nodes[3].translation().z,
+    })
+}
+
+impl PlayField {
+    /// Reads the limit nodes of stage.tscn. The spawn rails are not nodes and are passed in.
+    pub fn from_nodes(
+        stage: &Node,
+        spawn_top_z: f32,
+        spawn_side_x: f32,
+    ) -> Result<Self, PlayFieldError> {
+        let mut missing = Vec::new();
+        let inner = bounds_from_nodes(stage, &INNER_NODES, &mut missing);
+        let outer = bounds_from_nodes(stage, &OUTER_NODES, &mut missing);
+        match (inner, outer) {
+            (Some(inner), Some(outer)) => Ok(PlayField {
+                inner,
+                outer,
+                spawn_top_z,
+                spawn_side_x,
+            }),
+            _ => Err(PlayFieldError::MissingNodes(missing)),
+        }
+    }
+
+    /// Reads a ConfigFile with `[inner]` and `[outer]` left/right/up/down and `[spawn]` top_z/side_x.
+    pub fn from_file(path: &str) -> Result<Self, PlayFieldError> {
+        let config = ConfigFile::new();
+        if config.load(path).is_err() {
+            return Err(PlayFieldError::Load(path.to_string()));
+        }
+        let get = |section: &str, key: &str| -> Result<f32, PlayFieldError> {
+            if !config.has_section_key(section, key) {
+                return Err(PlayFieldError::MissingKey {
+                    path: path.to_string(),
+                    section: section.to_string(),
+                    key: key.to_string(),
+                });
+            }
+            Ok(config.get_value(section, key, Variant::new()).to_f64() as f32)
+        };
+        let bounds = |section: &str| -> Result<Bounds, PlayFieldError> {
+            Ok(Bounds {
+                left: get(section, BOUNDS_KEYS[0])?,
+                right: get(section, BOUNDS_KEYS[1])?,
+                up: get(section, BOUNDS_KEYS[2])?,
+                down: get(section, BOUNDS_KEYS[3])?,
+            })
+        };
+        Ok(PlayField {
+            inner: bounds("inner")?,
+            outer: bounds("outer")?,
+            spawn_top_z: get("spawn", "top_z")?,
+            spawn_side_x: get("spawn", "side_x")?,
+        })
+    }
+}
@@ -0,0 +1,1049 @@
+use gdnative::api::{Area, AudioStreamPlayer, CPUParticles, CollisionShape, MultiMeshInstance};
+use gdnative::prelude::*;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+use super::alien::Alien;
+use super::alien_bullet::AlienBullet;
+use super::bullet::Bullet;
+use super::bullet_field::BulletField;
+use super::input_config;
+use super::layer::{classify_hit_variant, validate_layers, HitKind, Layer};
+use super::magazine::{Magazine, PoolPolicy};
+use super::option_pod::{OptionFormation, OptionPod};
+use super::{areas_in_group, instance_scene, Env, ALIEN_BULLET_GROUP, ALIEN_GROUP, PLAYER_GROUP};
+use crate::autopilot::{Autopilot, Limits, Ship, Threat};
+use crate::barrage::barrage_name;
+use crate::events::DeathCause;
+use crate::replay::{Replay, ReplayInput};
+
+// left stick axes of the first joypad
+const JOY_AXIS_LX: i64 = 0;
+const JOY_AXIS_LY: i64 = 1;
+
+// largest deadzone, apply_response divides by 1 - deadzone
+const MAX_DEADZONE: f32 = 0.95;
+// smallest response exponent, at 0 or below a slight tilt would move at full speed or more
+const MIN_RESPONSE_CURVE: f32 = 0.1;
+
+// option pod placement
+const OPTION_SPREAD_X: f32 = 1.5;
+const OPTION_SPREAD_Z: f32 = 0.6;
+// physics frames between option pods in the snake formation
+const OPTION_TRAIL_GAP: usize = 8;
+
+// speed ratio while the focus action is held
+const FOCUS_SPEED_WEIGHT: f32 = 0.5;
+
+// speed ratio while the shoot action is held
+const SHOOT_SPEED_WEIGHT: f32 = 0.5;
+
+// hit radii the autopilot assumes, the Area shapes of the scenes
+const AUTOPILOT_PLAYER_RADIUS: f32 = 0.9;
+const AUTOPILOT_ALIEN_RADIUS: f32 = 0.8;
+const AUTOPILOT_ALIEN_BULLET_RADIUS: f32 = 0.25;
+
+// distance below down_limit where the player starts flying in on respawn
+const FLY_IN_DISTANCE: f32 = 6.0;
+
+/// Input source of a player while the Stage runs the demo.
+enum DemoControl {
+    Autopilot,
+    // the autopilot takes over past the end
+    Replay { replay: Replay, frame: u32 },
+}
+
+#[derive(NativeClass)]
+#[inherit(Area)]
+#[register_with(Self::register_signals)]
+pub struct Player {
+    #[property(default = 0)]
+    pub player_index: i32,
+    pub home: Vector3,
+    #[property(default = 100)]
+    bullet_num: i32,
+    #[property(default = 0)]
+    bullet_pool_policy: i32,
+    #[property(default = 300)]
+    bullet_pool_cap: i32,
+    // set by the Stage, bullets skip Area overlaps when true
+    pub rust_collision: bool,
+    #[property(default = 5.0)]
+    speed: f32,
+    setted_speed: f32,
+    #[property]
+    bullet_scene: Ref<PackedScene>,
+
+    magazine: Option<Magazine<Bullet>>,
+
+    left_barrel: Option<Ref<Spatial>>,
+    right_barrel: Option<Ref<Spatial>>,
+    laser: Option<Ref<AudioStreamPlayer>>,
+
+    pub env: Env,
+
+    pub alive: bool,
+    beated_sound: Option<Ref<AudioStreamPlayer, Unique>>,
+    collision_shape: Option<Ref<CollisionShape, Unique>>,
+    fighter: Option<Ref<Spatial, Unique>>,
+    frag: Option<Ref<CPUParticles, Unique>>,
+    destruct_timer: Option<Ref<Timer, Unique>>,
+    blink_timer: Option<Ref<Timer, Unique>>,
+    on_collision_timer: Option<Ref<Timer, Unique>>,
+
+    #[property(default = 3.0)]
+    invulnerable_time: f32,
+    #[property(default = 1.0)]
+    fly_in_time: f32,
+    #[property(default = true)]
+    clear_bullets_on_respawn: bool,
+    #[property(default = 0)]
+    shield: i32,
+    fly_in_left: f32,
+
+    #[property(default = 0.2, after_set = "Self::clamp_deadzone")]
+    deadzone: f32,
+    #[property(default = 1.5, after_set = "Self::clamp_response_curve")]
+    response_curve: f32,
+    analog_input: Vector2,
+
+    // flies by itself, for attract mode demos and difficulty tests
+    #[property(default = false)]
+    autopilot: bool,
+    pilot: Autopilot,
+    demo: Option<DemoControl>,
+    // inputs of the current run, set by the Stage
+    pub recording: Option<Replay>,
+    // what hit the player last, sent with player_beated
+    pub hit_by: DeathCause,
+
+    #[property]
+    option_scene: Ref<PackedScene>,
+    #[property(default = 4)]
+    max_options: i32,
+    options: Vec<Instance<OptionPod, Shared>>,
+    option_formation: OptionFormation,
+    locked_offsets: Vec<Vector3>,
+    trail: VecDeque<Vector3>,
+}
+
+#[gdnative::methods]
+impl Player {
+    fn new(_owner: &Area) -> Self {
+        Self {
+            player_index: 0,
+            home: Vector3::new(0.0, 0.0, 0.0),
+            bullet_num: 100,
+            bullet_pool_policy: 0,
+            bullet_pool_cap: 300,
+            rust_collision: false,
+            speed: 5.0,
+            setted_speed: 5.0,
+            bullet_scene: PackedScene::new().into_shared(),
+
+            magazine: None,
+
+            left_barrel: None,
+            right_barrel: None,
+            laser: None,
+
+            env: Env::new(),
+
+            alive: true,
+            beated_sound: None,
+            collision_shape: None,
+            fighter: None,
+            frag: None,
+            destruct_timer: None,
+            blink_timer: None,
+            on_collision_timer: None,
+
+            invulnerable_time: 3.0,
+            fly_in_time: 1.0,
+            clear_bullets_on_respawn: true,
+            shield: 0,
+            fly_in_left: 0.0,
+
+            deadzone: 0.2,
+            response_curve: 1.5,
+            analog_input: Vector2::new(0.0, 0.0),
+
+            autopilot: false,
+            pilot: Autopilot::default(),
+            demo: None,
+            recording: None,
+            hit_by: DeathCause::Unknown,
+
+            option_scene: PackedScene::new().into_shared(),
+            max_options: 4,
+            options: Vec::new(),
+            option_formation: OptionFormation::Spread,
+            locked_offsets: Vec::new(),
+            trail: VecDeque::new(),
+        }
+    }
+
+    fn register_signals(builder: &ClassBuilder<Self>) {
+        // godot_print!("register_signals@Player");
+
+        builder.add_signal(Signal {
+            name: "player_beated",
+            args: &[
+                SignalArgument {
+                    name: "player",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "cause",
+                    default: Variant::new(),
+                    export_info: ExportInfo::new(VariantType::Dictionary),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "player_crashed",
+            args: &[SignalArgument {
+                name: "player",
+                default: Variant::from_i64(0),
+                export_info: ExportInfo::new(VariantType::I64),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        builder.add_signal(Signal {
+            name: "player_respawned",
+            args: &[SignalArgument {
+                name: "player",
+                default: Variant::from_i64(0),
+                export_info: ExportInfo::new(VariantType::I64),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        builder.add_signal(Signal {
+            name: "options_changed",
+            args: &[SignalArgument {
+                name: "num",
+                default: Variant::from_i64(0),
+                export_info: ExportInfo::new(VariantType::I64),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        builder.add_signal(Signal {
+            name: "shield_consumed",
+            args: &[SignalArgument {
+                name: "remain",
+                default: Variant::from_i64(0),
+                export_info: ExportInfo::new(VariantType::I64),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+
+        // godot_print!("end register_signals@Player");
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Area) {
+        // godot_print!("start _ready@Player");
+        validate_layers(owner, Layer::PLAYER, Layer::ALIEN | Layer::ALIEN_BULLET);
+        if let Some(wall) = unsafe { owner.get_node_as::<Area>("BulletWall") } {
+            validate_layers(&wall, Layer::NONE, Layer::BULLET);
+        }
+        self.setted_speed = self.speed;
+        self.magazine = Some(Magazine::with_policy(
+            &self.bullet_scene,
+            self.bullet_num as usize,
+            PoolPolicy::from_property(
+                self.bullet_pool_policy,
+                self.bullet_num / 4,
+                self.bullet_pool_cap,
+            ),
+        ));
+
+        self.left_barrel = Some(unsafe {
+            owner
+                .get_node_as::<Spatial>("fighter/LeftBarrel")
+                .unwrap()
+                .claim()
+        });
+        self.right_barrel = Some(unsafe {
+            owner
+                .get_node_as::<Spatial>("fighter/RightBarrel")
+                .unwrap()
+                .claim()
+        });
+        self.laser = Some(unsafe {
+            owner
+                .get_node_as::<AudioStreamPlayer>("laser")
+                .unwrap()
+                .claim()
+        });
+
+        self.alive = false;
+        self.beated_sound = Some(unsafe {
+            owner
+                .get_node_as::<AudioStreamPlayer>("beatedSound")
+                .unwrap()
+                .claim()
+                .assume_unique()
+        });
+        self.collision_shape = Some(unsafe {
+            owner
+                .get_node_as::<CollisionShape>("CollisionShape")
+                .unwrap()
+                .claim()
+                .assume_unique()
+        });
+        self.fighter = Some(unsafe {
+            owner
+                .get_node_as::<Spatial>("fighter")
+                .unwrap()
+                .claim()
+                .assume_unique()
+        });
+        self.frag = Some(unsafe {
+            owner
+                .get_node_as::<CPUParticles>("frag")
+                .unwrap()
+                .claim()
+                .assume_unique()
+        });
+        self.destruct_timer = Some(unsafe {
+            owner
+                .get_node_as::<Timer>("DestructTimer")
+                .unwrap()
+                .claim()
+                .assume_unique()
+        });
+        self.blink_timer = Some(unsafe {
+            owner
+                .get_node_as::<Timer>("BlinkTimer")
+                .unwrap()
+                .claim()
+                .assume_unique()
+        });
+        self.on_collision_timer = Some(unsafe {
+            owner
+                .get_node_as::<Timer>("OnCollisionTimer")
+                .unwrap()
+                .claim()
+                .assume_unique()
+        });
+
+        // self.reset(owner);
+        self.alive = true; // for demonstration
+
+        owner.add_to_group(PLAYER_GROUP, false);
+
+        let stage = unsafe { owner.get_node("/root/stage").unwrap().assume_safe() };
+        for signal in ["player_beated", "player_crashed", "player_respawned"].iter() {
+            owner
+                .connect(*signal, stage, *signal, VariantArray::new_shared(), 0)
+                .unwrap();
+        }
+
+        godot_print!("_ready@Player {}", env!("CARGO_PKG_VERSION")); // info log
+    }
+
+    #[export]
+    fn reset(&mut self, owner: &Area) {
+        // godot_print!("reset@Player");
+        self.alive = true;
+        // fly in from below the screen
+        self.fly_in_left = self.fly_in_time;
+        owner.set_translation(self.fly_in_start());
+        self.speed = self.setted_speed;
+        if let Some(s) = self.fighter.as_ref() {
+            s.set_visible(true);
+            // godot_print!("reset visibility");
+        }
+        // OnCollisionTimer enables it again when the invulnerable time is over
+        self.disable_collision(owner);
+        if let Some(t) = self.destruct_timer.as_ref() {
+            t.stop();
+        }
+        self.clear_options(owner);
+        if self.clear_bullets_on_respawn {
+            Self::clear_alien_bullets(owner);
+        }
+        self.start_invulnerable(self.fly_in_time + self.invulnerable_time);
+    }
+
+    fn fly_in_start(&self) -> Vector3 {
+        Vector3::new(self.home.x, 0.0, self.env.down_limit + FLY_IN_DISTANCE)
+    }
+
+    fn fly_in(&mut self, owner: &Area, delta: f64) {
+        self.fly_in_left -= delta as f32;
+        let y = owner.translation().y;
+        if self.fly_in_left <= 0.0 {
+            self.fly_in_left = 0.0;
+            owner.set_translation(Vector3::new(self.home.x, y, self.home.z));
+            owner.emit_signal(
+                "player_respawned",
+                &[Variant::from_i64(self.player_index as i64)],
+            );
+            return;
+        }
+        // ease out: fast at first, slow down near the home position
+        let r = self.fly_in_left / self.fly_in_time;
+        let z = self.home.z + (self.fly_in_start().z - self.home.z) * r * r;
+        owner.set_translation(Vector3::new(self.home.x, y, z));
+    }
+
+    fn is_flying_in(&self) -> bool {
+        self.fly_in_left > 0.0
+    }
+
+    fn start_invulnerable(&self, time: f32) {
+        if let Some(t) = self.blink_timer.as_ref() {
+            t.start(0.0);
+        }
+        if let Some(t) = self.on_collision_timer.as_ref() {
+            t.start(time as f64);
+        }
+    }
+
+    fn clear_alien_bullets(owner: &Area) {
+        if let Some(tree) = owner.get_tree() {
+            let tree = unsafe { tree.assume_safe() };
+            tree.call_group(ALIEN_BULLET_GROUP, "vanish", &[]);
+        }
+    }
+
+    #[export]
+    fn add_shield(&mut self, _owner: &Area, num: i32) {
+        self.shield += num;
+    }
+
+    #[export]
+    fn blink(&mut self, _owner: &Area) {
+        if let Some(fighter) = self.fighter.as_ref() {
+            fighter.set_visible(!fighter.is_visible());
+        }
+    }
+
+    #[export]
+    fn enable_collision(&mut self, _owner: &Area) {
+        if let Some(c) = self.collision_shape.as_ref() {
+            c.set_disabled(false);
+        }
+        if let Some(fighter) = self.fighter.as_ref() {
+            fighter.set_visible(true);
+        }
+        if let Some(t) = self.blink_timer.as_ref() {
+            t.stop();
+        }
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Area, delta: f64) {
+        self.env.time += delta as f32;
+        self.env.theta = self.env.time / 0.8 * PI; // 0.8sで一周
+        if self.env.theta > 2.0 * PI {
+            self.env.theta -= 2.0 * PI;
+        }
+
+        let (stick, shoot, focus) = if let Some(control) = self.demo_control(owner) {
+            control
+        } else if self.autopilot {
+            self.autopilot_control(owner)
+        } else {
+            let input = Input::godot_singleton();
+            let actions = input_config::player_actions(self.player_index as usize);
+            let stick = self.apply_response(self.input_vector(input));
+            let shoot = input.is_action_pressed(actions.shoot);
+            let focus = input.is_action_pressed(actions.focus);
+            if input.is_action_just_pressed(actions.formation) {
+                self.cycle_option_formation(owner);
+            }
+            if let Some(replay) = self.recording.as_mut() {
+                let pushed = replay.push(ReplayInput {
+                    x: stick.x,
+                    z: stick.y,
+                    shoot,
+                    focus,
+                });
+                if let Err(e) = pushed {
+                    godot_warn!("{}, the run is no longer recorded", e);
+                    self.recording = None;
+                }
+            }
+            (stick, shoot, focus)
+        };
+
+        let sp_weight = if shoot {
+            self.shoot(owner);
+            SHOOT_SPEED_WEIGHT
+        } else {
+            1.0
+        };
+        let sp_weight = if focus {
+            sp_weight * FOCUS_SPEED_WEIGHT
+        } else {
+            sp_weight
+        };
+
+        self.wave_move(owner);
+        if self.is_flying_in() {
+            self.fly_in(owner, delta);
+            return;
+        }
+        self.move_control(owner, delta, stick, sp_weight);
+        self.coordinate_modifying(owner);
+        self.update_options(owner);
+    }
+
+    fn wave_move(&self, owner: &Area) {
+        let d = self.env.theta.cos() * 0.005;
+        let v = Vector3::new(0.0, d, 0.0);
+        // owner.move_and_collide(v, false, false, false);
+        owner.translate(v);
+    }
+
+    fn move_control(&self, owner: &Area, delta: f64, stick: Vector2, speed_weight: f32) {
+        if !self.alive {
+            return;
+        }
+
+        let v = Vector3::new(stick.x, 0.0, stick.y) * self.speed * speed_weight;
+        let fighter = self.fighter.as_ref().unwrap();
+        #[allow(non_upper_case_globals)]
+        const max: f32 = PI / 16.0;
+        if v.x > 0.0 {
+            fighter.rotate(Vector3::new(0.0, 0.0, 1.0), -delta);
+            if fighter.rotation().z < -max {
+                fighter.set_rotation(Vector3::new(0.0, 0.0, -max));
+            }
+        } else if v.x < 0.0 {
+            fighter.rotate(Vector3::new(0.0, 0.0, 1.0), delta);
+            if fighter.rotation().z > max {
+                fighter.set_rotation(Vector3::new(0.0, 0.0, max));
+            }
+        } else {
+            fighter.set_rotation(Vector3::new(0.0, 0.0, 0.0));
+        }
+        owner.translate(v * delta as f32);
+        // owner.move_and_collide(v * delta as f32, false, false, false);
+    }
+
+    /// The strongest of the digital actions, the joypad stick and the vector pushed from GDScript.
+    fn input_vector(&self, input: &Input) -> Vector2 {
+        let actions = input_config::player_actions(self.player_index as usize);
+        let action = Vector2::new(
+            (input.get_action_strength(actions.move_right)
+                - input.get_action_strength(actions.move_left)) as f32,
+            (input.get_action_strength(actions.move_down)
+                - input.get_action_strength(actions.move_up)) as f32,
+        );
+        let device = self.player_index as i64;
+        let axis = Vector2::new(
+            input.get_joy_axis(device, JOY_AXIS_LX) as f32,
+            input.get_joy_axis(device, JOY_AXIS_LY) as f32,
+        );
+        [action, axis, self.analog_input]
+            .iter()
+            .fold(Vector2::new(0.0, 0.0), |acc, &v| {
+                if v.length() > acc.length() {
+                    v
+                } else {
+                    acc
+                }
+            })
+    }
+
+    fn apply_response(&self, v: Vector2) -> Vector2 {
+        let len = v.length();
+        if len <= self.deadzone {
+            return Vector2::new(0.0, 0.0);
+        }
+        let t = (len.min(1.0) - self.deadzone) / (1.0 - self.deadzone);
+        v / len * t.powf(self.response_curve)
+    }
+
+    fn clamp_deadzone(&mut self, _owner: TRef<Area>) {
+        self.deadzone = self.deadzone.max(0.0).min(MAX_DEADZONE);
+    }
+
+    fn clamp_response_curve(&mut self, _owner: TRef<Area>) {
+        self.response_curve = self.response_curve.max(MIN_RESPONSE_CURVE);
+    }
+
+    #[export]
+    fn set_analog_input(&mut self, _owner: &Area, vec: Vector2) {
+        self.analog_input = vec;
+    }
+
+    #[export]
+    fn set_autopilot(&mut self, _owner: &Area, enabled: bool) {
+        self.autopilot = enabled;
+    }
+
+    pub fn start_demo(&mut self, replay: Option<Replay>) {
+        self.demo = Some(match replay {
+            Some(replay) => DemoControl::Replay { replay, frame: 0 },
+            None => DemoControl::Autopilot,
+        });
+        self.recording = None;
+    }
+
+    pub fn stop_demo(&mut self) {
+        self.demo = None;
+    }
+
+    fn demo_control(&mut self, owner: &Area) -> Option<(Vector2, bool, bool)> {
+        let input = match self.demo.as_mut()? {
+            DemoControl::Replay { replay, frame } => {
+                let input = replay.input(*frame);
+                *frame += 1;
+                input
+            }
+            DemoControl::Autopilot => None,
+        };
+        Some(match input {
+            Some(i) => (Vector2::new(i.x, i.z), i.shoot, i.focus),
+            None => self.autopilot_control(owner),
+        })
+    }
+
+    // stick, shoot and focus picked by the autopilot from the bullets and aliens in the tree
+    fn autopilot_control(&self, owner: &Area) -> (Vector2, bool, bool) {
+        let pos = owner.translation();
+        let ship = Ship {
+            x: pos.x,
+            z: pos.z,
+            r: AUTOPILOT_PLAYER_RADIUS,
+            speed: self.speed,
+            focus_speed: self.speed * FOCUS_SPEED_WEIGHT,
+            shoot_weight: SHOOT_SPEED_WEIGHT,
+        };
+        let limits = Limits {
+            left: self.env.left_limit,
+            right: self.env.right_limit,
+            up: self.env.up_limit,
+            down: self.env.down_limit,
+        };
+        let (threats, targets) = autopilot_view(owner);
+        let d = self.pilot.decide(&ship, &limits, &threats, &targets);
+        (Vector2::new(d.x, d.z), d.shoot, d.focus)
+    }
+
+    fn coordinate_modifying(&self, owner: &Area) {
+        if owner.translation().x < self.env.left_limit {
+            owner.set_translation(Vector3::new(
+                self.env.left_limit,
+                owner.translation().y,
+                owner.translation().z,
+            ));
+        }
+        if owner.translation().x > self.env.right_limit {
+            owner.set_translation(Vector3::new(
+                self.env.right_limit,
+                owner.translation().y,
+                owner.translation().z,
+            ));
+        }
+        if owner.translation().z > self.env.down_limit {
+            owner.set_translation(Vector3::new(
+                owner.translation().x,
+                owner.translation().y,
+                self.env.down_limit,
+            ));
+        }
+        if owner.translation().z < self.env.up_limit {
+            owner.set_translation(Vector3::new(
+                owner.translation().x,
+                owner.translation().y,
+                self.env.up_limit,
+            ));
+        }
+    }
+
+    #[export]
+    fn get_pool_stats(&self, _owner: &Area) -> Dictionary {
+        self.get_magazine().stats().to_dictionary()
+    }
+
+    pub fn get_magazine(&self) -> &Magazine<Bullet> {
+        self.magazine.as_ref().unwrap()
+    }
+
+    pub fn mut_magazine(&mut self) -> &mut Magazine<Bullet> {
+        self.magazine.as_mut().unwrap()
+    }
+
+    #[export]
+    fn crash(&mut self, owner: &Area, other_area_var: Variant) {
+        // godot_print!("crash beep");
+        if !self.alive {
+            return;
+        }
+
+        let area = other_area_var.try_to_object::<Area>();
+        self.hit_by = match classify_hit_variant(&other_area_var) {
+            HitKind::Alien => area
+                .and_then(Instance::<Alien, _>::from_base)
+                .and_then(|a| {
+                    unsafe { a.assume_safe() }
+                        .map(|a, _| DeathCause::Alien {
+                            kind: a.kind.clone(),
+                            id: a.id,
+                        })
+                        .ok()
+                })
+                .unwrap_or_default(),
+            HitKind::AlienBullet => area
+                .and_then(Instance::<AlienBullet, _>::from_base)
+                .and_then(|b| {
+                    unsafe { b.assume_safe() }
+                        .map(|b, _| b.origin.clone().map(DeathCause::Bullet))
+                        .ok()
+                        .flatten()
+                })
+                .unwrap_or_default(),
+            _ => return,
+        };
+
+        // godot_print!("crash@Player");
+        self.take_hit(owner);
+    }
+
+    /// Hit by a bullet of the BulletField, which has no Area to pass to `crash`.
+    /// The Stage sets `hit_by` first.
+    #[export]
+    fn bullet_hit(&mut self, owner: &Area) {
+        if !self.collidable() {
+            return;
+        }
+        self.take_hit(owner);
+    }
+
+    fn take_hit(&mut self, owner: &Area) {
+        if self.shield > 0 {
+            self.shield -= 1;
+            owner.emit_signal("shield_consumed", &[Variant::from_i64(self.shield as i64)]);
+            self.start_invulnerable(self.invulnerable_time);
+            unsafe {
+                owner.call_deferred("disable_collision", &[]);
+            }
+            return;
+        }
+
+        self.speed = 0.0;
+        self.alive = false;
+        self.frag.as_ref().unwrap().set_emitting(true);
+        self.beated_sound.as_ref().unwrap().play(0.0);
+        self.fighter.as_ref().unwrap().set_visible(false);
+        self.destruct_timer.as_ref().unwrap().start(0.0);
+        self.clear_options(owner);
+        owner.emit_signal(
+            "player_crashed",
+            &[Variant::from_i64(self.player_index as i64)],
+        );
+
+        unsafe {
+            owner.call_deferred("disable_collision", &[]);
+        }
+    }
+
+    #[export]
+    fn alert_beated(&self, owner: &Area) {
+        owner.emit_signal(
+            "player_beated",
+            &[
+                Variant::from_i64(self.player_index as i64),
+                death_cause_dictionary(&self.hit_by).to_variant(),
+            ],
+        );
+    }
+
+    #[export]
+    fn disable_collision(&mut self, _owner: &Area) {
+        self.collision_shape.as_ref().unwrap().set_disabled(true);
+    }
+
+    pub fn collidable(&self) -> bool {
+        self.alive
+            && self
+                .collision_shape
+                .as_ref()
+                .map(|c| !c.is_disabled())
+                .unwrap_or(false)
+    }
+
+    fn shoot(&mut self, owner: &Area) {
+        if !self.alive || self.is_flying_in() {
+            return;
+        }
+
+        let t = (self.env.time * 100.0) as u64;
+
+        let barrel = unsafe {
+            if t % 2 == 0 {
+                self.left_barrel.as_ref().unwrap().assume_safe()
+            } else {
+                self.right_barrel.as_ref().unwrap().assume_safe()
+            }
+        };
+        let pos = barrel.global_transform().origin;
+
+        if !self.fire_bullet(owner, pos) {
+            return;
+        }
+
+        // options fire along with the left barrel
+        if t % 2 == 0 {
+            let option_positions: Vec<Vector3> = self
+                .options
+                .iter()
+                .filter_map(|pod| {
+                    unsafe { pod.assume_safe() }
+                        .map(|_, o| o.translation())
+                        .ok()
+                })
+                .collect();
+            for pos in option_positions {
+                self.fire_bullet(owner, pos);
+            }
+        }
+
+        unsafe { self.laser.as_ref().unwrap().assume_safe() }.play(0.0);
+    }
+
+    fn fire_bullet(&mut self, owner: &Area, pos: Vector3) -> bool {
+        let player_index = self.player_index;
+        let shooter = owner.claim();
+        let area_collision = !self.rust_collision;
+        let bullet_scene = match self.mut_magazine().hammer() {
+            Some(b) => {
+                unsafe { b.assume_safe() }
+                    .map_mut(|bb, b_owner| {
+                        b_owner.set_monitoring(area_collision);
+                        b_owner.set_monitorable(area_collision);
+                        b_owner.set_translation(pos);
+                        bb.flying = true;
+                        bb.player_index = player_index;
+                        bb.shooter = Some(shooter);
+                    })
+                    .ok();
+                b.into_base()
+            }
+            None => return false,
+        };
+
+        // let bullet_scene: Ref<Area, _> = instance_scene(&self.bullet_scene);
+        if let Some(parent) = owner.get_parent() {
+            let parent = unsafe { parent.assume_safe() };
+            parent.add_child(bullet_scene, false);
+        }
+        true
+    }
+
+    #[export]
+    fn power_up(&mut self, owner: &Area) {
+        if !self.alive || self.options.len() >= self.max_options as usize {
+            return;
+        }
+        let pod = match instance_scene::<Spatial>(&self.option_scene) {
+            Some(pod) => pod,
+            None => return,
+        };
+        pod.set_translation(owner.translation());
+        let pod = pod.into_shared();
+        if let Some(parent) = owner.get_parent() {
+            let parent = unsafe { parent.assume_safe() };
+            parent.add_child(pod.clone(), false);
+        }
+        match Instance::from_base(pod.clone()) {
+            Some(pod) => {
+                let i = self.options.len();
+                self.options.push(pod);
+                self.locked_offsets.push(Self::spread_offset(i));
+            }
+            None => {
+                godot_error!("option_scene is not an OptionPod");
+                unsafe { pod.assume_safe() }.queue_free();
+                return;
+            }
+        }
+        owner.emit_signal(
+            "options_changed",
+            &[Variant::from_i64(self.options.len() as i64)],
+        );
+    }
+
+    fn clear_options(&mut self, owner: &Area) {
+        for pod in self.options.drain(..) {
+            unsafe { pod.into_base().assume_safe() }.queue_free();
+        }
+        self.locked_offsets.clear();
+        self.trail.clear();
+        owner.emit_signal("options_changed", &[Variant::from_i64(0)]);
+    }
+
+    #[export]
+    fn set_option_formation(&mut self, owner: &Area, formation: i64) {
+        self.option_formation = OptionFormation::from_i64(formation);
+        if self.option_formation == OptionFormation::Locked {
+            // keep the options where they are relative to the ship
+            let pos = owner.translation();
+            self.locked_offsets = self
+                .options
+                .iter()
+                .enumerate()
+                .map(|(i, pod)| {
+                    unsafe { pod.assume_safe() }
+                        .map(|_, o| o.translation() - pos)
+                        .unwrap_or_else(|_| Self::spread_offset(i))
+                })
+                .collect();
+        }
+    }
+
+    #[export]
+    fn cycle_option_formation(&mut self, owner: &Area) {
+        let next = self.option_formation.next() as i64;
+        self.set_option_formation(owner, next);
+    }
+
+    fn spread_offset(i: usize) -> Vector3 {
+        let side = if i % 2 == 0 { -1.0 } else { 1.0 };
+        let rank = (i / 2 + 1) as f32;
+        Vector3::new(side * OPTION_SPREAD_X * rank, 0.0, OPTION_SPREAD_Z * rank)
+    }
+
+    fn option_target(&self, i: usize, pos: Vector3) -> Vector3 {
+        match self.option_formation {
+            OptionFormation::Spread => pos + Self::spread_offset(i),
+            OptionFormation::Snake => self
+                .trail
+                .get((i + 1) * OPTION_TRAIL_GAP)
+                .or_else(|| self.trail.back())
+                .copied()
+                .unwrap_or(pos),
+            OptionFormation::Locked => {
+                pos + self
+                    .locked_offsets
+                    .get(i)
+                    .copied()
+                    .unwrap_or_else(|| Self::spread_offset(i))
+            }
+        }
+    }
+
+    fn update_options(&mut self, owner: &Area) {
+        let pos = owner.translation();
+        // the snake only advances while the ship is moving
+        if self
+            .trail
+            .front()
+            .map_or(true, |p| (*p - pos).length() > 0.01)
+        {
+            self.trail.push_front(pos);
+            self.trail
+                .truncate(self.max_options.max(0) as usize * OPTION_TRAIL_GAP + 1);
+        }
+
+        for (i, pod) in self.options.iter().enumerate() {
+            let target = self.env.clamp(self.option_target(i, pos));
+            unsafe { pod.assume_safe() }
+                .map_mut(|o, _| o.target = target)
+                .ok();
+        }
+    }
+
+    #[export]
+    fn collect_bullet(&mut self, _owner: &Area, bullet_var: Variant, generation: u64) {
+        if let Some(bullet_area) = bullet_var.try_to_object::<Area>() {
+            self.mut_magazine().charge_bullet(bullet_area, generation);
+        }
+    }
+}
+
+/// The `cause` argument of `player_beated`, `text` is what the game over screen shows.
+fn death_cause_dictionary(cause: &DeathCause) -> Dictionary {
+    let dict = Dictionary::new();
+    dict.insert("cause", cause.name());
+    dict.insert("text", cause.describe());
+    match cause {
+        DeathCause::Alien { kind, id } => {
+            dict.insert("alien_kind", kind.as_str());
+            dict.insert("alien_id", *id);
+        }
+        DeathCause::Bullet(origin) => {
+            dict.insert("barrage", origin.barrage as i64);
+            dict.insert("barrage_name", barrage_name(origin.barrage));
+            dict.insert("volley", origin.volley as i64);
+            dict.insert("spawn_tick", origin.spawn_tick as i64);
+            dict.insert("alien_kind", origin.alien_kind.as_str());
+            dict.insert("alien_id", origin.alien_id);
+        }
+        DeathCause::Unknown => {}
+    }
+    dict.into_shared()
+}
+
+// alien bullets and aliens as the autopilot sees them, and the aliens to shoot at
+fn autopilot_view(owner: &Node) -> (Vec<Threat>, Vec<(f32, f32)>) {
+    let mut threats = Vec::new();
+    let mut targets = Vec::new();
+    for area in areas_in_group(owner, ALIEN_BULLET_GROUP) {
+        let velocity = Instance::<AlienBullet, _>::from_base(area.clone())
+            .and_then(|b| unsafe { b.assume_safe() }.map(|b, _| b.velocity()).ok())
+            .flatten();
+        if let Some(v) = velocity {
+            let pos = unsafe { area.assume_safe() }.global_transform().origin;
+            threats.push(Threat::new(
+                pos.x,
+                pos.z,
+                v.x,
+                v.z,
+                AUTOPILOT_ALIEN_BULLET_RADIUS,
+            ));
+        }
+    }
+    if let Some(tree) = owner.get_tree() {
+        for node in unsafe { tree.assume_safe() }
+            .get_nodes_in_group(ALIEN_BULLET_GROUP)
+            .iter()
+            .filter_map(|v| v.try_to_object::<MultiMeshInstance>())
+        {
+            if let Some(field) = Instance::<BulletField, _>::from_base(node) {
+                unsafe { field.assume_safe() }
+                    .map(|f, _| threats.extend(f.threats()))
+                    .ok();
+            }
+        }
+    }
+    for area in areas_in_group(owner, ALIEN_GROUP) {
+        let velocity = Instance::<Alien, _>::from_base(area.clone())
+            .and_then(|a| unsafe { a.assume_safe() }.map(|a, _| a.velocity()).ok())
+            .flatten();
+        if let Some(v) = velocity {
+            let pos = unsafe { area.assume_safe() }.global_transform().origin;
+            threats.push(Threat::new(pos.x, pos.z, v.x, v.z, AUTOPILOT_ALIEN_RADIUS));
+            targets.push((pos.x, pos.z));
+        }
+    }
+    (threats, targets)
+}
+
+pub fn nearest_living_player(owner: &Area, pos: Vector3) -> Option<Vector3> {
+    let tree = unsafe { owner.get_tree()?.assume_safe() };
+    tree.get_nodes_in_group(PLAYER_GROUP)
+        .iter()
+        .filter_map(|v| {
+            let player = Instance::<Player, _>::from_base(v.try_to_object::<Area>()?)?;
+            unsafe { player.assume_safe() }
+                .map(|p, o| if p.alive { Some(o.translation()) } else { None })
+                .ok()
+                .flatten()
+        })
+        .min_by(|a, b| {
+            (*a - pos)
+                .length()
+                .partial_cmp(&(*b - pos).length())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+}
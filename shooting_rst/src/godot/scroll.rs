@@ -0,0 +1,93 @@
+use gdnative::api::Curve3D;
+use gdnative::prelude::*;
+
+/// Position of the play field frame along the stage.
+///
+/// The frame itself stays at the origin and the world node moves the other way,
+/// so spawn positions, `Env` limits and `AlienEnv` checks keep working in frame coordinates.
+/// Only the rotation-free part of a path is followed.
+pub struct ScrollFrame {
+    pub speed: f32,
+    dir: Vector3,
+    path: Option<Ref<Curve3D, Shared>>,
+    travelled: f32,
+    position: Vector3,
+}
+
+impl ScrollFrame {
+    pub fn new() -> Self {
+        ScrollFrame {
+            speed: 0.0,
+            dir: Vector3::new(0.0, 0.0, -1.0),
+            path: None,
+            travelled: 0.0,
+            position: Vector3::zero(),
+        }
+    }
+
+    /// Scrolls straight along `dir`, e.g. (0, 0, -1) for vertical and (1, 0, 0) for horizontal stages.
+    pub fn set_dir(&mut self, dir: Vector3) {
+        let dir = dir.normalize();
+        self.dir = if dir.is_finite() {
+            dir
+        } else {
+            Vector3::new(0.0, 0.0, -1.0)
+        };
+    }
+
+    /// Follows `path` instead of a straight line.
+    pub fn set_path(&mut self, path: Option<Ref<Curve3D, Shared>>) {
+        self.path = path;
+    }
+
+    pub fn is_scrolling(&self) -> bool {
+        self.speed != 0.0
+    }
+
+    pub fn travelled(&self) -> f32 {
+        self.travelled
+    }
+
+    pub fn position(&self) -> Vector3 {
+        self.position
+    }
+
+    fn path_length(&self) -> Option<f32> {
+        let path = self.path.as_ref()?;
+        Some(unsafe { path.assume_safe() }.get_baked_length() as f32)
+    }
+
+    fn position_at(&self, travelled: f32) -> Vector3 {
+        match self.path.as_ref() {
+            Some(path) => {
+                let path = unsafe { path.assume_safe() };
+                path.interpolate_baked(travelled as f64, false) - path.interpolate_baked(0.0, false)
+            }
+            None => self.dir * travelled,
+        }
+    }
+
+    /// Moves the frame forward. Returns `false` once the end of the path has been reached.
+    pub fn advance(&mut self, delta: f32) -> bool {
+        let mut travelled = self.travelled + self.speed * delta;
+        let mut running = true;
+        if let Some(length) = self.path_length() {
+            if travelled >= length {
+                travelled = length;
+                running = false;
+            }
+        }
+        self.travelled = travelled.max(0.0);
+        self.position = self.position_at(self.travelled);
+        running
+    }
+
+    pub fn reset(&mut self) {
+        self.travelled = 0.0;
+        self.position = Vector3::zero();
+    }
+
+    pub fn to_world(&self, frame_pos: Vector3) -> Vector3 {
+        frame_pos + self.position
+    }
+}
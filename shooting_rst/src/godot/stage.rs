@@ -0,0 +1,1932 @@
+use gdnative::api::{
+    Area, AudioServer, AudioStreamPlayer, Engine, File, InputEvent, InputEventJoypadMotion, Path,
+    ProjectSettings, RandomNumberGenerator, OS,
+};
+use gdnative::prelude::*;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use super::alien::{Alien, AlienProcessPattern};
+use super::alien_bullet::AlienBullet;
+use super::bullet::Bullet;
+use super::bullet_field::BulletField;
+use super::leaderboard::Leaderboard;
+use super::magazine::{Magazine, PoolPolicy};
+use super::play_field::{PlayField, PlayFieldError};
+use super::player::Player;
+use super::scroll::ScrollFrame;
+use super::telemetry::Telemetry;
+use super::{
+    areas_in_group, barrage_dirs, instance_scene, AlienEnv, Env, ALIEN_BULLET_GROUP, ALIEN_GROUP,
+    PLAYER_BULLET_GROUP,
+};
+use crate::collision::{self, Circle, SpatialHash};
+use crate::events::{BulletOrigin, DeathCause, Event, EventRing, Record};
+use crate::leaderboard::Submission;
+use crate::replay::{Replay, Rules};
+use crate::telemetry::{Death, RunSummary};
+
+// frames a reset may wait for the magazines to be refilled before it is reported
+const RESET_WAIT_FRAMES: u32 = 60;
+
+// kills needed for a power up
+const POWER_UP_SPAN: i32 = 10;
+
+// x of each player's home position in co-op
+const COOP_HOME_X: f32 = 3.0;
+
+// stick tilt that counts as input while the demo runs
+const DEMO_CANCEL_AXIS: f32 = 0.5;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum GameState {
+    Title,
+    Playing,
+    Paused,
+    PlayerDying,
+    StageClear,
+    GameOver,
+    Continue,
+    Demo,
+    /// waiting for every magazine to be full before the next run
+    Resetting,
+}
+
+impl GameState {
+    fn name(self) -> &'static str {
+        match self {
+            GameState::Title => "Title",
+            GameState::Playing => "Playing",
+            GameState::Paused => "Paused",
+            GameState::PlayerDying => "PlayerDying",
+            GameState::StageClear => "StageClear",
+            GameState::GameOver => "GameOver",
+            GameState::Continue => "Continue",
+            GameState::Demo => "Demo",
+            GameState::Resetting => "Resetting",
+        }
+    }
+
+    fn can_transition_to(self, next: GameState) -> bool {
+        use GameState::*;
+        matches!(
+            (self, next),
+            (Title, Playing)
+                | (Title, Demo)
+                | (Demo, Title)
+                | (Playing, Paused)
+                | (Playing, PlayerDying)
+                | (Playing, StageClear)
+                | (Playing, GameOver)
+                | (Paused, Playing)
+                | (Paused, PlayerDying)
+                | (Paused, Title)
+                | (Paused, Resetting)
+                | (PlayerDying, Playing)
+                | (PlayerDying, Paused)
+                | (PlayerDying, Continue)
+                | (PlayerDying, GameOver)
+                | (StageClear, Playing)
+                | (StageClear, Title)
+                | (Continue, Playing)
+                | (Continue, GameOver)
+                | (GameOver, Title)
+                | (GameOver, Resetting)
+                | (Title, Resetting)
+                | (Resetting, Playing)
+        )
+    }
+}
+
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(Self::register_signals)]
+pub struct Stage {
+    #[property(default = 100)]
+    alien_num: i32,
+    #[property]
+    alien_scene: Ref<PackedScene>,
+    aliens_magazine: Option<Magazine<Alien>>,
+    #[property(default = 0)]
+    alien_pool_policy: i32,
+    #[property(default = 200)]
+    alien_pool_cap: i32,
+
+    alien_spawn_timer: Option<Ref<Timer, Unique>>,
+    rng: Option<Ref<RandomNumberGenerator, Unique>>,
+
+    env: Env,
+    alien_env: AlienEnv,
+    play_field: PlayField,
+    // ConfigFile with the play field, the limit nodes are used when empty
+    #[property]
+    play_field_path: String,
+    #[property(default = -27.0)]
+    spawn_top_z: f32,
+    #[property(default = 22.0)]
+    spawn_side_x: f32,
+
+    // 0 keeps the arena fixed
+    #[property(default = 0.0)]
+    scroll_speed: f32,
+    #[property]
+    scroll_dir: Vector3,
+    // Path node the frame follows instead of scroll_dir
+    #[property]
+    scroll_path: NodePath,
+    scroll: ScrollFrame,
+    // terrain and ground enemies, moved against the frame
+    world: Option<Ref<Spatial, Shared>>,
+
+    #[property(default = 3)]
+    player_life: i32,
+    #[property(default = 1)]
+    player_num: i32,
+    #[property]
+    player_scene: Ref<PackedScene>,
+    players: Vec<PlayerSlot>,
+    beated_alien_num: i32,
+    #[property(default = 1.0)]
+    stage_heat: f32,
+
+    #[property(default = 200)]
+    alibullet_num: i32,
+    #[property]
+    alibullet_scene: Ref<PackedScene>,
+    alibullets_magazine: Option<Magazine<AlienBullet>>,
+    #[property(default = 0)]
+    alibullet_pool_policy: i32,
+    #[property(default = 400)]
+    alibullet_pool_cap: i32,
+    #[property(default = 50)]
+    pool_grow_step: i32,
+    // objects out longer than this are reported as leaked, 0 disables the check
+    #[property(default = 0)]
+    pool_leak_msec: i64,
+
+    // test bullets in Rust instead of with Godot Area overlaps
+    #[property(default = false)]
+    rust_collision: bool,
+    #[property(default = 2.0)]
+    collision_cell_size: f32,
+    #[property(default = 0.9)]
+    player_hit_radius: f32,
+    #[property(default = 0.8)]
+    alien_hit_radius: f32,
+    #[property(default = 0.1)]
+    bullet_hit_radius: f32,
+    #[property(default = 0.25)]
+    alibullet_hit_radius: f32,
+    collision_grid: SpatialHash,
+
+    // alien bullets live in a BulletField instead of the magazine
+    #[property(default = false)]
+    multimesh_bullets: bool,
+    bullet_field: Option<Instance<BulletField, Shared>>,
+
+    bgm: Option<Ref<AudioStreamPlayer, Unique>>,
+    extend_sound: Option<Ref<AudioStreamPlayer, Unique>>,
+
+    state: GameState,
+    state_before_pause: GameState,
+    reset_wait_frames: Option<u32>,
+
+    #[property(default = 3)]
+    credits: i32,
+    credits_left: i32,
+    #[property(default = 10)]
+    continue_time: i32,
+    continue_left: i32,
+    #[property(default = true)]
+    continue_resets_score: bool,
+    continue_count: i32,
+    continue_timer: Option<Ref<Timer, Unique>>,
+
+    // seconds on the title before the demo starts, 0 disables it
+    #[property(default = 15.0)]
+    demo_idle_time: f32,
+    #[property(default = 30.0)]
+    demo_time: f32,
+    // replay the first player follows in the demo, the autopilot flies when empty
+    #[property]
+    demo_replay_path: String,
+    // where the first player's replay of each run is saved, empty disables recording
+    #[property]
+    replay_record_path: String,
+    idle_time: f32,
+    demo_left: f32,
+    input_seen: bool,
+    muted_before_demo: bool,
+    next_seed: Option<u64>,
+
+    // JSON Lines of gameplay events, empty disables the file
+    #[property]
+    event_log_path: String,
+    // latest events kept for dump_events, 0 keeps none
+    #[property(default = 1000)]
+    event_ring_size: i32,
+    events: EventRing,
+    event_file: Option<std::fs::File>,
+    // physics frames since the stage was ready
+    tick: u64,
+    alien_kind: String,
+    next_alien_id: i64,
+    next_volley: u64,
+    // shown on the game over screen
+    last_death: Option<DeathCause>,
+
+    // opt-in, run summaries are POSTed here, empty sends nothing
+    #[property]
+    telemetry_endpoint: String,
+    // summaries not sent yet
+    #[property]
+    telemetry_queue_path: String,
+    run_summary: Option<RunSummary>,
+    run_start_tick: u64,
+    telemetry: Option<Instance<Telemetry, Shared>>,
+
+    // the online leaderboard, empty disables it
+    #[property]
+    leaderboard_url: String,
+    leaderboard: Option<Instance<Leaderboard, Shared>>,
+    // the last finished run of the first player, for submit_score
+    last_run: Option<FinishedRun>,
+}
+
+struct FinishedRun {
+    replay: Replay,
+    score: i32,
+    kills: i32,
+    seconds: f32,
+}
+
+#[gdnative::methods]
+impl Stage {
+    fn new(_owner: &Node) -> Self {
+        Stage {
+            alien_num: 100,
+            alien_scene: PackedScene::new().into_shared(),
+            aliens_magazine: None,
+            alien_pool_policy: 0,
+            alien_pool_cap: 200,
+            alien_spawn_timer: None,
+            rng: None,
+            env: Env::new(),
+            alien_env: AlienEnv::new(),
+            play_field: PlayField::default(),
+            play_field_path: String::new(),
+            spawn_top_z: -27.0,
+            spawn_side_x: 22.0,
+
+            scroll_speed: 0.0,
+            scroll_dir: Vector3::new(0.0, 0.0, -1.0),
+            scroll_path: NodePath::default(),
+            scroll: ScrollFrame::new(),
+            world: None,
+
+            player_life: 3,
+            player_num: 1,
+            player_scene: PackedScene::new().into_shared(),
+            players: Vec::new(),
+            beated_alien_num: 0,
+            stage_heat: 1.0,
+
+            alibullet_num: 200,
+            alibullet_scene: PackedScene::new().into_shared(),
+            alibullets_magazine: None,
+            alibullet_pool_policy: 0,
+            alibullet_pool_cap: 400,
+            pool_grow_step: 50,
+            pool_leak_msec: 0,
+
+            rust_collision: false,
+            collision_cell_size: 2.0,
+            player_hit_radius: 0.9,
+            alien_hit_radius: 0.8,
+            bullet_hit_radius: 0.1,
+            alibullet_hit_radius: 0.25,
+            collision_grid: SpatialHash::new(2.0),
+
+            multimesh_bullets: false,
+            bullet_field: None,
+
+            bgm: None,
+            extend_sound: None,
+
+            state: GameState::Title,
+            state_before_pause: GameState::Playing,
+            reset_wait_frames: None,
+
+            credits: 3,
+            credits_left: 3,
+            continue_time: 10,
+            continue_left: 0,
+            continue_resets_score: true,
+            continue_count: 0,
+            continue_timer: None,
+
+            demo_idle_time: 15.0,
+            demo_time: 30.0,
+            demo_replay_path: String::new(),
+            replay_record_path: String::new(),
+            idle_time: 0.0,
+            demo_left: 0.0,
+            input_seen: false,
+            muted_before_demo: false,
+            next_seed: None,
+
+            event_log_path: String::new(),
+            event_ring_size: 1000,
+            events: EventRing::new(0),
+            event_file: None,
+            tick: 0,
+            alien_kind: String::new(),
+            next_alien_id: 0,
+            next_volley: 0,
+            last_death: None,
+
+            telemetry_endpoint: String::new(),
+            telemetry_queue_path: "user://telemetry.jsonl".to_string(),
+            run_summary: None,
+            run_start_tick: 0,
+            telemetry: None,
+
+            leaderboard_url: String::new(),
+            leaderboard: None,
+            last_run: None,
+        }
+    }
+
+    fn register_signals(builder: &ClassBuilder<Self>) {
+        // godot_print!("register_signals@Stage");
+
+        builder.add_signal(Signal {
+            name: "restart_player",
+            args: &[],
+        });
+        builder.add_signal(Signal {
+            name: "speed_up",
+            args: &[SignalArgument {
+                name: "speed",
+                default: Variant::from_f64(1.0),
+                export_info: ExportInfo::new(VariantType::F64),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+
+        builder.add_signal(Signal {
+            name: "set_score",
+            args: &[
+                SignalArgument {
+                    name: "score",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "player",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "set_remain",
+            args: &[
+                SignalArgument {
+                    name: "remain",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "player",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "game_over",
+            args: &[SignalArgument {
+                name: "cause",
+                default: Variant::from_str(""),
+                export_info: ExportInfo::new(VariantType::GodotString),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        builder.add_signal(Signal {
+            name: "pool_exhausted",
+            args: &[SignalArgument {
+                name: "pool",
+                default: Variant::from_str(""),
+                export_info: ExportInfo::new(VariantType::GodotString),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        builder.add_signal(Signal {
+            name: "continue_offered",
+            args: &[
+                SignalArgument {
+                    name: "seconds_left",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+                SignalArgument {
+                    name: "credits",
+                    default: Variant::from_i64(0),
+                    export_info: ExportInfo::new(VariantType::I64),
+                    usage: PropertyUsage::DEFAULT,
+                },
+            ],
+        });
+        builder.add_signal(Signal {
+            name: "continue_taken",
+            args: &[SignalArgument {
+                name: "continue_count",
+                default: Variant::from_i64(0),
+                export_info: ExportInfo::new(VariantType::I64),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+        builder.add_signal(Signal {
+            name: "scroll_finished",
+            args: &[],
+        });
+        builder.add_signal(Signal {
+            name: "state_changed",
+            args: &[SignalArgument {
+                name: "state",
+                default: Variant::from_str(GameState::Title.name()),
+                export_info: ExportInfo::new(VariantType::GodotString),
+                usage: PropertyUsage::DEFAULT,
+            }],
+        });
+
+        // godot_print!("end register_signals@Stage");
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Node) {
+        // godot_print!("start _ready@Stage");
+        let rng = RandomNumberGenerator::new();
+        rng.randomize();
+        let alien_spawn_timer = unsafe {
+            let t = owner.get_node_as::<Timer>("alien_spawn_timer").unwrap();
+            // t.start(rng.randf_range(0.5, 1.0));
+            t.claim().assume_unique()
+        };
+        self.rng = Some(rng);
+        self.alien_spawn_timer = Some(alien_spawn_timer);
+        self.collision_grid = SpatialHash::new(self.collision_cell_size);
+        self.aliens_magazine = Some(Magazine::with_policy(
+            &self.alien_scene,
+            self.alien_num as usize,
+            PoolPolicy::from_property(
+                self.alien_pool_policy,
+                self.pool_grow_step,
+                self.alien_pool_cap,
+            ),
+        ));
+        self.play_field = match self.load_play_field(owner) {
+            Ok(field) => field,
+            Err(e) => {
+                godot_error!("{}, using the default play field", e);
+                PlayField::default()
+            }
+        };
+        self.env.init(&self.play_field);
+        self.alien_env.init(&self.play_field);
+        self.init_scroll(owner);
+        self.init_event_log();
+        self.init_telemetry(owner);
+        self.init_leaderboard(owner);
+
+        let alibullet_policy = PoolPolicy::from_property(
+            self.alibullet_pool_policy,
+            self.pool_grow_step,
+            self.alibullet_pool_cap,
+        );
+        self.alibullets_magazine = Some(Magazine::with_policy(
+            &self.alibullet_scene,
+            self.alibullet_num as usize,
+            alibullet_policy,
+        ));
+
+        if self.multimesh_bullets {
+            // as many bullets as the magazine could hand out
+            let capacity = match alibullet_policy {
+                PoolPolicy::Grow { cap, .. } => cap.max(self.alibullet_num as usize) as i32,
+                _ => self.alibullet_num,
+            };
+            let field = Instance::<BulletField, Unique>::new();
+            field
+                .map_mut(|f, _| {
+                    f.capacity = capacity;
+                    f.radius = self.alibullet_hit_radius;
+                    f.set_play_field(&self.play_field);
+                })
+                .ok();
+            let field = field.into_shared();
+            owner.add_child(field.base().clone(), false);
+            self.bullet_field = Some(field);
+        }
+
+        let bgm = unsafe {
+            let bgm = owner.get_node_as::<AudioStreamPlayer>("BGM").unwrap();
+            // bgm.play(0.0);
+            bgm.claim().assume_unique()
+        };
+        self.bgm = Some(bgm);
+        self.extend_sound = Some(unsafe {
+            owner
+                .get_node_as::<AudioStreamPlayer>("extend")
+                .unwrap()
+                .claim()
+                .assume_unique()
+        });
+
+        // counts down while the tree is paused for the continue
+        let continue_timer = Timer::new();
+        continue_timer.set_wait_time(1.0);
+        continue_timer.set_pause_mode(Node::PAUSE_MODE_PROCESS);
+        continue_timer
+            .connect(
+                "timeout",
+                unsafe { owner.assume_shared() },
+                "continue_tick",
+                VariantArray::new_shared(),
+                0,
+            )
+            .unwrap();
+        let continue_timer = continue_timer.into_shared();
+        owner.add_child(continue_timer.clone(), false);
+        self.continue_timer = Some(unsafe { continue_timer.assume_unique() });
+
+        // self.start_game(owner);
+
+        let player = unsafe { owner.get_node_as::<Area>("PlayerRoot").unwrap().claim() };
+        self.players.push(PlayerSlot::new(player));
+        if self.player_num >= 2 {
+            if let Some(player) = self.spawn_second_player(owner) {
+                self.players.push(PlayerSlot::new(player));
+            }
+        }
+
+        let player_num = self.players.len();
+        let rust_collision = self.rust_collision;
+        let field = self.play_field;
+        for (i, slot) in self.players.iter().enumerate() {
+            let home = if player_num > 1 {
+                Vector3::new(if i == 0 { -COOP_HOME_X } else { COOP_HOME_X }, 0.0, 0.0)
+            } else {
+                Vector3::new(0.0, 0.0, 0.0)
+            };
+            if let Some(player) = Instance::<Player, _>::from_base(slot.node.clone()) {
+                unsafe { player.assume_safe() }
+                    .map_mut(|p, _| {
+                        p.home = home;
+                        p.rust_collision = rust_collision;
+                        p.env.init(&field);
+                    })
+                    .ok();
+            }
+            let node = unsafe { slot.node.assume_safe() };
+            owner
+                .connect(
+                    "restart_player",
+                    node,
+                    "reset",
+                    VariantArray::new_shared(),
+                    0,
+                )
+                .unwrap();
+        }
+
+        // godot_print!("stage ready");
+    }
+
+    fn init_scroll(&mut self, owner: &Node) {
+        self.scroll.set_dir(self.scroll_dir);
+        if !self.scroll_path.is_empty() {
+            match unsafe { owner.get_node_as::<Path>(self.scroll_path.new_ref()) } {
+                Some(path) => self.scroll.set_path(path.curve()),
+                None => godot_error!(
+                    "scroll path {} is not a Path node",
+                    self.scroll_path.to_godot_string()
+                ),
+            }
+        }
+
+        let world = match unsafe { owner.get_node_as::<Spatial>("World") } {
+            Some(world) => world.claim(),
+            None => {
+                let world = Spatial::new();
+                world.set_name("World");
+                let world = world.into_shared();
+                owner.add_child(world.clone(), false);
+                world
+            }
+        };
+        self.world = Some(world);
+    }
+
+    fn load_play_field(&self, owner: &Node) -> Result<PlayField, PlayFieldError> {
+        if self.play_field_path.is_empty() {
+            PlayField::from_nodes(owner, self.spawn_top_z, self.spawn_side_x)
+        } else {
+            PlayField::from_file(&self.play_field_path)
+        }
+    }
+
+    fn spawn_second_player(&self, owner: &Node) -> Option<Ref<Area, Shared>> {
+        let player = instance_scene::<Area>(&self.player_scene)?;
+        let player = Instance::<Player, _>::from_base(player)?;
+        player.map_mut(|p, _| p.player_index = 1).ok();
+        let player = player.into_base();
+        player.set_name("PlayerRoot2");
+        let player = player.into_shared();
+        owner.add_child(player.clone(), false);
+        Some(player)
+    }
+
+    fn change_state(&mut self, owner: &Node, next: GameState) -> bool {
+        if !self.state.can_transition_to(next) {
+            godot_warn!("invalid state transition: {:?} -> {:?}", self.state, next);
+            return false;
+        }
+        self.state = next;
+        owner.emit_signal("state_changed", &[Variant::from_str(next.name())]);
+        true
+    }
+
+    fn set_tree_paused(owner: &Node, paused: bool) {
+        if let Some(tree) = owner.get_tree() {
+            unsafe { tree.assume_safe() }.set_pause(paused);
+        }
+    }
+
+    #[export]
+    fn get_state(&self, _owner: &Node) -> String {
+        self.state.name().to_string()
+    }
+
+    #[export]
+    fn toggle_pause(&mut self, owner: &Node) {
+        match self.state {
+            GameState::Playing | GameState::PlayerDying => {
+                let before = self.state;
+                if self.change_state(owner, GameState::Paused) {
+                    self.state_before_pause = before;
+                    Self::set_tree_paused(owner, true);
+                }
+            }
+            GameState::Paused => {
+                if self.change_state(owner, self.state_before_pause) {
+                    Self::set_tree_paused(owner, false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Removes every alien and bullet on the field. They go back to their magazines.
+    fn clear_field(owner: &Node) {
+        if let Some(tree) = owner.get_tree() {
+            let tree = unsafe { tree.assume_safe() };
+            tree.call_group(ALIEN_GROUP, "destruct", &[]);
+            tree.call_group(ALIEN_BULLET_GROUP, "vanish", &[]);
+            tree.call_group(PLAYER_BULLET_GROUP, "vanish", &[]);
+        }
+    }
+
+    fn stop_game(&mut self) {
+        if let Some(timer) = self.alien_spawn_timer.as_ref() {
+            timer.stop();
+        }
+        if let Some(bgm) = self.bgm.as_ref() {
+            bgm.stop();
+        }
+    }
+
+    #[export]
+    fn restart_game(&mut self, owner: &Node) {
+        if !matches!(self.state, GameState::GameOver | GameState::Paused)
+            || !self.change_state(owner, GameState::Resetting)
+        {
+            return;
+        }
+        godot_print!("game restart"); // info log
+        Self::set_tree_paused(owner, false);
+        self.start_reset(owner);
+    }
+
+    /// Sends every live alien and bullet back to its magazine,
+    /// then starts a new run once all the magazines are full again.
+    /// Only from the title or the game over screen, `restart_game` resets a paused run.
+    #[export]
+    fn reset_run(&mut self, owner: &Node) {
+        if !matches!(self.state, GameState::Title | GameState::GameOver) {
+            godot_warn!("reset_run while {}, ignored", self.state.name());
+            return;
+        }
+        if self.change_state(owner, GameState::Resetting) {
+            self.start_reset(owner);
+        }
+    }
+
+    fn start_reset(&mut self, owner: &Node) {
+        self.stop_game();
+        Self::clear_field(owner);
+        // the objects return to the magazines with deferred calls
+        self.reset_wait_frames = Some(0);
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Node, delta: f64) {
+        if self.pool_leak_msec > 0 {
+            self.check_pool_leaks();
+        }
+        self.update_demo(owner, delta as f32);
+
+        if self.state != GameState::Resetting {
+            return;
+        }
+        let frames = self.reset_wait_frames.unwrap_or(0);
+        if !self.magazines_full() {
+            // the run waits however long it takes, a pool that never refills is a leak
+            if frames == RESET_WAIT_FRAMES {
+                godot_warn!(
+                    "magazines are not refilled after {} frames: aliens {}/{}, alien bullets {}/{}",
+                    RESET_WAIT_FRAMES,
+                    self.get_alien_magazine().get_left_num(),
+                    self.get_alien_magazine().get_capacity(),
+                    self.get_alibul_magazine().get_left_num(),
+                    self.get_alibul_magazine().get_capacity(),
+                );
+            }
+            self.reset_wait_frames = Some(frames.saturating_add(1));
+            return;
+        }
+        self.reset_wait_frames = None;
+        if self.change_state(owner, GameState::Playing) {
+            self.begin_run(owner);
+        }
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Node, delta: f64) {
+        self.tick += 1;
+        if self.scroll.is_scrolling()
+            && matches!(
+                self.state,
+                GameState::Playing | GameState::PlayerDying | GameState::Demo
+            )
+        {
+            self.scroll_world(owner, delta as f32);
+        }
+        if self.rust_collision {
+            self.collide_bullets(owner);
+        }
+        if self.bullet_field.is_some() {
+            self.collide_bullet_field();
+        }
+    }
+
+    fn scroll_world(&mut self, owner: &Node, delta: f32) {
+        let running = self.scroll.advance(delta);
+        if let Some(world) = self.world.as_ref() {
+            unsafe { world.assume_safe() }.set_translation(-self.scroll.position());
+        }
+        if !running {
+            self.scroll.speed = 0.0;
+            owner.emit_signal("scroll_finished", &[]);
+        }
+    }
+
+    /// Changes the scroll speed, for scripted stages.
+    #[export]
+    fn set_scroll_speed(&mut self, _owner: &Node, speed: f32) {
+        self.scroll.speed = speed;
+    }
+
+    #[export]
+    fn get_scroll_distance(&self, _owner: &Node) -> f32 {
+        self.scroll.travelled()
+    }
+
+    /// Places terrain or a ground enemy at `frame_pos` of the current frame.
+    /// It stays put in the world and scrolls out with it.
+    #[export]
+    fn spawn_ground(
+        &self,
+        _owner: &Node,
+        scene: Ref<PackedScene>,
+        frame_pos: Vector3,
+    ) -> Option<Ref<Spatial>> {
+        let world = unsafe { self.world.as_ref()?.assume_safe() };
+        let node = instance_scene::<Spatial>(&scene)?;
+        node.set_translation(self.scroll.to_world(frame_pos));
+        let node = node.into_shared();
+        world.add_child(node.clone(), false);
+        Some(node)
+    }
+
+    fn reset_scroll(&mut self) {
+        self.scroll.reset();
+        self.scroll.speed = self.scroll_speed;
+        if let Some(world) = self.world.as_ref() {
+            let world = unsafe { world.assume_safe() };
+            world.set_translation(Vector3::zero());
+            for child in world.get_children().iter() {
+                if let Some(child) = child.try_to_object::<Node>() {
+                    unsafe { child.assume_safe() }.queue_free();
+                }
+            }
+        }
+    }
+
+    fn collidable_players(&self) -> Vec<Ref<Area, Shared>> {
+        self.players
+            .iter()
+            .filter(|slot| slot.alive)
+            .map(|slot| slot.node.clone())
+            .filter(|p| {
+                Instance::<Player, _>::from_base(p.clone())
+                    .and_then(|p| unsafe { p.assume_safe() }.map(|p, _| p.collidable()).ok())
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    fn collide_bullet_field(&mut self) {
+        let field = match self.bullet_field.as_ref() {
+            Some(field) => unsafe { field.assume_safe() },
+            None => return,
+        };
+        let players = self.collidable_players();
+        let targets = circles(&players, self.player_hit_radius);
+        let grid = &mut self.collision_grid;
+        // (player, where the bullet came from)
+        let hits: Vec<(usize, Option<BulletOrigin>)> = field
+            .map_mut(|f, _| {
+                let hits = collision::collide(grid, &f.circles(), &targets);
+                let origins = hits
+                    .iter()
+                    .map(|&(b, p)| (p, f.origin(b).cloned()))
+                    .collect();
+                let mut bullets: Vec<_> = hits.iter().map(|&(b, _)| b).collect();
+                f.remove(&mut bullets);
+                origins
+            })
+            .unwrap_or_default();
+
+        let mut hit_players: Vec<usize> = Vec::new();
+        for (p, origin) in hits {
+            if hit_players.contains(&p) {
+                continue;
+            }
+            hit_players.push(p);
+            if let Some(player) = Instance::<Player, _>::from_base(players[p].clone()) {
+                unsafe { player.assume_safe() }
+                    .map_mut(|player, _| {
+                        player.hit_by = origin.map(DeathCause::Bullet).unwrap_or_default()
+                    })
+                    .ok();
+            }
+            unsafe {
+                players[p].assume_safe().call_deferred("bullet_hit", &[]);
+            }
+        }
+    }
+
+    fn collide_bullets(&mut self, owner: &Node) {
+        let bullets: Vec<_> = areas_in_group(owner, PLAYER_BULLET_GROUP)
+            .into_iter()
+            .filter(|b| {
+                Instance::<Bullet, _>::from_base(b.clone())
+                    .and_then(|b| unsafe { b.assume_safe() }.map(|b, _| b.flying).ok())
+                    .unwrap_or(false)
+            })
+            .collect();
+        // no BulletWall hits without Area overlaps
+        for b in bullets.iter() {
+            let b = unsafe { b.assume_safe() };
+            if self.alien_env.gone_far_away(&b) {
+                unsafe {
+                    b.call_deferred("vanish", &[]);
+                }
+            }
+        }
+        let aliens: Vec<_> = areas_in_group(owner, ALIEN_GROUP)
+            .into_iter()
+            .filter(|a| {
+                Instance::<Alien, _>::from_base(a.clone())
+                    .and_then(|a| unsafe { a.assume_safe() }.map(|a, _| a.alive).ok())
+                    .unwrap_or(false)
+            })
+            .collect();
+        let hits = collision::collide(
+            &mut self.collision_grid,
+            &circles(&bullets, self.bullet_hit_radius),
+            &circles(&aliens, self.alien_hit_radius),
+        );
+        for (b, a) in hits {
+            dispatch_hit(&bullets[b], "hit", &aliens[a], "shooted");
+        }
+
+        let alibullets: Vec<_> = areas_in_group(owner, ALIEN_BULLET_GROUP)
+            .into_iter()
+            .filter(|b| {
+                Instance::<AlienBullet, _>::from_base(b.clone())
+                    .and_then(|b| unsafe { b.assume_safe() }.map(|b, _| b.flying).ok())
+                    .unwrap_or(false)
+            })
+            .collect();
+        let players = self.collidable_players();
+        let hits = collision::collide(
+            &mut self.collision_grid,
+            &circles(&alibullets, self.alibullet_hit_radius),
+            &circles(&players, self.player_hit_radius),
+        );
+        for (b, p) in hits {
+            dispatch_hit(&alibullets[b], "hit", &players[p], "crash");
+        }
+    }
+
+    fn check_pool_leaks(&mut self) {
+        let threshold = self.pool_leak_msec;
+        let aliens = self.mut_alien_magazine().check_leaks(threshold);
+        if aliens > 0 {
+            godot_warn!("{} aliens are out longer than {} msec", aliens, threshold);
+        }
+        let alibullets = self.mut_alibul_magazine().check_leaks(threshold);
+        if alibullets > 0 {
+            godot_warn!(
+                "{} alien bullets are out longer than {} msec",
+                alibullets,
+                threshold
+            );
+        }
+        for (i, slot) in self.players.iter().enumerate() {
+            let bullets = Instance::<Player, _>::from_base(slot.node.clone())
+                .and_then(|p| {
+                    unsafe { p.assume_safe() }
+                        .map_mut(|p, _| p.mut_magazine().check_leaks(threshold))
+                        .ok()
+                })
+                .unwrap_or(0);
+            if bullets > 0 {
+                godot_warn!(
+                    "{} bullets of player {} are out longer than {} msec",
+                    bullets,
+                    i + 1,
+                    threshold
+                );
+            }
+        }
+    }
+
+    /// Pool counters keyed by pool name, for a debug overlay.
+    #[export]
+    fn get_pool_stats(&self, _owner: &Node) -> Dictionary {
+        let dict = Dictionary::new();
+        dict.insert("aliens", self.get_alien_magazine().stats().to_dictionary());
+        dict.insert(
+            "alien_bullets",
+            self.get_alibul_magazine().stats().to_dictionary(),
+        );
+        for (i, slot) in self.players.iter().enumerate() {
+            let stats = Instance::<Player, _>::from_base(slot.node.clone()).and_then(|p| {
+                unsafe { p.assume_safe() }
+                    .map(|p, _| p.get_magazine().stats())
+                    .ok()
+            });
+            if let Some(stats) = stats {
+                dict.insert(format!("player{}_bullets", i + 1), stats.to_dictionary());
+            }
+        }
+        dict.into_shared()
+    }
+
+    fn magazines_full(&self) -> bool {
+        self.get_alien_magazine().is_full()
+            && self.get_alibul_magazine().is_full()
+            && self.players.iter().all(|slot| {
+                Instance::<Player, _>::from_base(slot.node.clone())
+                    .and_then(|p| {
+                        unsafe { p.assume_safe() }
+                            .map(|p, _| p.get_magazine().is_full())
+                            .ok()
+                    })
+                    .unwrap_or(true)
+            })
+    }
+
+    #[export]
+    fn return_to_title(&mut self, owner: &Node) {
+        if !self.change_state(owner, GameState::Title) {
+            return;
+        }
+        Self::set_tree_paused(owner, false);
+        self.stop_game();
+        Self::clear_field(owner);
+        if let Some(replay) = self.take_replay() {
+            self.save_replay(&replay);
+        }
+        self.finish_run("quit");
+    }
+
+    /// Any key, button, touch or stick tilt, for starting and stopping the demo.
+    #[export]
+    fn _input(&mut self, _owner: &Node, event: Ref<InputEvent>) {
+        let event = unsafe { event.assume_safe() };
+        let tilted = event
+            .cast::<InputEventJoypadMotion>()
+            .map(|m| (m.axis_value().abs() as f32) >= DEMO_CANCEL_AXIS)
+            .unwrap_or(false);
+        if event.is_pressed() || tilted {
+            self.input_seen = true;
+        }
+    }
+
+    fn update_demo(&mut self, owner: &Node, delta: f32) {
+        let input_seen = std::mem::replace(&mut self.input_seen, false);
+        match self.state {
+            GameState::Title => {
+                if input_seen || self.demo_idle_time <= 0.0 {
+                    self.idle_time = 0.0;
+                    return;
+                }
+                self.idle_time += delta;
+                if self.idle_time >= self.demo_idle_time {
+                    self.start_demo(owner);
+                }
+            }
+            GameState::Demo => {
+                self.demo_left -= delta;
+                if input_seen || self.demo_left <= 0.0 {
+                    self.stop_demo(owner);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Plays the game by itself with the sound off, through the same spawn and fire code as a run.
+    #[export]
+    fn start_demo(&mut self, owner: &Node) {
+        if !self.change_state(owner, GameState::Demo) {
+            return;
+        }
+        self.idle_time = 0.0;
+        self.demo_left = self.demo_time;
+
+        let replay = if self.demo_replay_path.is_empty() {
+            None
+        } else {
+            match load_replay(&self.demo_replay_path) {
+                Ok(replay) => Some(replay),
+                Err(e) => {
+                    godot_error!("{}, the autopilot flies the demo", e);
+                    None
+                }
+            }
+        };
+        self.next_seed = replay.as_ref().map(|r| r.seed);
+        for (i, slot) in self.players.iter().enumerate() {
+            let replay = if i == 0 { replay.clone() } else { None };
+            if let Some(player) = Instance::<Player, _>::from_base(slot.node.clone()) {
+                unsafe { player.assume_safe() }
+                    .map_mut(|p, _| p.start_demo(replay))
+                    .ok();
+            }
+        }
+
+        let audio = AudioServer::godot_singleton();
+        self.muted_before_demo = audio.is_bus_mute(0);
+        audio.set_bus_mute(0, true);
+        self.begin_run(owner);
+    }
+
+    #[export]
+    fn stop_demo(&mut self, owner: &Node) {
+        if self.state != GameState::Demo {
+            return;
+        }
+        for slot in self.players.iter() {
+            if let Some(player) = Instance::<Player, _>::from_base(slot.node.clone()) {
+                unsafe { player.assume_safe() }
+                    .map_mut(|p, _| p.stop_demo())
+                    .ok();
+            }
+        }
+        AudioServer::godot_singleton().set_bus_mute(0, self.muted_before_demo);
+        self.return_to_title(owner);
+        self.idle_time = 0.0;
+    }
+
+    // the first player's recording of the run that just ended
+    fn take_replay(&mut self) -> Option<Replay> {
+        self.players
+            .first()
+            .and_then(|slot| {
+                Instance::<Player, _>::from_base(slot.node.clone()).and_then(|p| {
+                    unsafe { p.assume_safe() }
+                        .map_mut(|p, _| p.recording.take())
+                        .ok()
+                        .flatten()
+                })
+            })
+            .filter(|replay| !replay.is_empty())
+    }
+
+    // hands the recording to replay_record_path
+    fn save_replay(&self, replay: &Replay) {
+        if self.replay_record_path.is_empty() {
+            return;
+        }
+        let file = File::new();
+        if file
+            .open(self.replay_record_path.as_str(), File::WRITE)
+            .is_err()
+        {
+            godot_error!("failed to write replay {}", self.replay_record_path);
+            return;
+        }
+        file.store_string(replay.to_string());
+        file.close();
+    }
+
+    fn init_event_log(&mut self) {
+        self.events = EventRing::new(self.event_ring_size.max(0) as usize);
+        let scene_path = unsafe { self.alien_scene.assume_safe() }
+            .resource_path()
+            .to_string();
+        // res://alien.tscn -> alien
+        self.alien_kind = scene_path
+            .rsplit('/')
+            .next()
+            .and_then(|name| name.split('.').next())
+            .unwrap_or("")
+            .to_string();
+        if self.event_log_path.is_empty() {
+            return;
+        }
+        // std instead of File so every line is on disk when the game dies
+        let path = ProjectSettings::godot_singleton()
+            .globalize_path(self.event_log_path.as_str())
+            .to_string();
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => self.event_file = Some(file),
+            Err(e) => godot_error!("failed to open event log {}: {}", path, e),
+        }
+    }
+
+    fn log_event(&mut self, event: Event) {
+        let record = Record {
+            tick: self.tick,
+            msec: OS::godot_singleton().get_ticks_msec() as u64,
+            event,
+        };
+        if let Some(file) = self.event_file.as_mut() {
+            if let Err(e) = writeln!(file, "{}", record.to_json()) {
+                godot_error!("failed to write event log: {}", e);
+                self.event_file = None;
+            }
+        }
+        self.events.push(record);
+    }
+
+    fn pool_exhausted(&mut self, owner: &Node, pool: &str) {
+        self.log_event(Event::PoolExhausted {
+            pool: pool.to_string(),
+        });
+        owner.emit_signal("pool_exhausted", &[Variant::from_str(pool)]);
+    }
+
+    /// Writes the latest events as JSON Lines, for bug reports.
+    #[export]
+    fn dump_events(&self, _owner: &Node, path: String) -> bool {
+        let file = File::new();
+        if file.open(path.as_str(), File::WRITE).is_err() {
+            godot_error!("failed to write events {}", path);
+            return false;
+        }
+        file.store_string(self.events.to_json_lines());
+        file.close();
+        true
+    }
+
+    fn run_seconds(&self) -> f32 {
+        let fps = Engine::godot_singleton().iterations_per_second().max(1) as f32;
+        (self.tick - self.run_start_tick) as f32 / fps
+    }
+
+    /// Queues the summary of the run in progress and sends the queue.
+    fn finish_run(&mut self, ended: &str) {
+        let mut summary = match self.run_summary.take() {
+            Some(summary) => summary,
+            None => return,
+        };
+        summary.seconds = self.run_seconds();
+        summary.scores = self.players.iter().map(|slot| slot.score).collect();
+        summary.kills = self.beated_alien_num;
+        summary.continues = self.continue_count;
+        summary.ended = ended.to_string();
+        if let Some(telemetry) = self.telemetry.as_ref() {
+            unsafe { telemetry.assume_safe() }
+                .map_mut(|t, _| t.queue(&summary))
+                .ok();
+        }
+    }
+
+    // the Telemetry child sends whatever an earlier session could not when it is ready
+    fn init_telemetry(&mut self, owner: &Node) {
+        if self.telemetry_endpoint.is_empty() {
+            return;
+        }
+        let telemetry = Instance::<Telemetry, Unique>::new();
+        telemetry
+            .map_mut(|t, _| {
+                t.endpoint = self.telemetry_endpoint.clone();
+                t.queue_path = self.telemetry_queue_path.clone();
+            })
+            .ok();
+        telemetry.base().set_name("Telemetry");
+        let telemetry = telemetry.into_shared();
+        owner.add_child(telemetry.base().clone(), false);
+        self.telemetry = Some(telemetry);
+    }
+
+    fn init_leaderboard(&mut self, owner: &Node) {
+        if self.leaderboard_url.is_empty() {
+            return;
+        }
+        let leaderboard = Instance::<Leaderboard, Unique>::new();
+        leaderboard
+            .map_mut(|l, _| l.base_url = self.leaderboard_url.clone())
+            .ok();
+        leaderboard.base().set_name("Leaderboard");
+        let leaderboard = leaderboard.into_shared();
+        owner.add_child(leaderboard.base().clone(), false);
+        self.leaderboard = Some(leaderboard);
+    }
+
+    /// Submits the last finished run under `name`. The answer comes as the
+    /// `submitted` or `request_failed` signal of the Leaderboard child.
+    #[export]
+    fn submit_score(&mut self, _owner: &Node, name: String) -> bool {
+        let (leaderboard, run) = match (self.leaderboard.as_ref(), self.last_run.as_ref()) {
+            (Some(leaderboard), Some(run)) => (leaderboard, run),
+            _ => {
+                godot_warn!("no run to submit");
+                return false;
+            }
+        };
+        let submission = Submission::new(&name, run.score, run.kills, run.seconds, &run.replay);
+        unsafe { leaderboard.assume_safe() }
+            .map_mut(|l, owner| l.submit(&owner, &submission))
+            .is_ok()
+    }
+
+    /// Asks for the top standings, answered by the `standings_received` signal of the Leaderboard child.
+    #[export]
+    fn fetch_leaderboard(&mut self, _owner: &Node) {
+        if let Some(leaderboard) = self.leaderboard.as_ref() {
+            unsafe { leaderboard.assume_safe() }
+                .map_mut(|l, owner| l.fetch_top(&owner))
+                .ok();
+        }
+    }
+
+    #[export]
+    fn clear_stage(&mut self, owner: &Node) {
+        if !self.change_state(owner, GameState::StageClear) {
+            return;
+        }
+        if let Some(timer) = self.alien_spawn_timer.as_ref() {
+            timer.stop();
+        }
+        Self::clear_field(owner);
+    }
+
+    #[export]
+    fn next_stage(&mut self, owner: &Node) {
+        if self.state != GameState::StageClear || !self.change_state(owner, GameState::Playing) {
+            return;
+        }
+        if let (Some(timer), Some(rng)) = (self.alien_spawn_timer.as_ref(), self.rng.as_ref()) {
+            timer.start(rng.randf_range(0.5, 1.0));
+        }
+    }
+
+    /// Starts a run from the title at once. From the game over screen the field is reset first,
+    /// like `reset_run`. A cleared stage goes on with `next_stage`.
+    #[export]
+    fn start_game(&mut self, owner: &Node) {
+        match self.state {
+            GameState::Title => {
+                if self.change_state(owner, GameState::Playing) {
+                    godot_print!("game start"); // info log
+                    self.begin_run(owner);
+                }
+            }
+            GameState::GameOver => {
+                if self.change_state(owner, GameState::Resetting) {
+                    godot_print!("game start"); // info log
+                    self.start_reset(owner);
+                }
+            }
+            // the HUD sends game_start every frame the button is held
+            _ => {}
+        }
+    }
+
+    fn begin_run(&mut self, owner: &Node) {
+        self.finish_run("restart");
+        self.last_death = None;
+        self.credits_left = self.credits;
+        self.continue_count = 0;
+        for i in 0..self.players.len() {
+            self.set_player_life(owner, i, self.player_life);
+            self.set_player_score(owner, i, 0);
+            self.players[i].alive = true;
+        }
+        self.set_beated_alien_num(owner, 0);
+        self.set_heat(owner, 1.0);
+        self.reset_scroll();
+        let rng = match self.rng.as_ref() {
+            Some(rng) => rng,
+            None => return,
+        };
+        // a seed per run so the first player's inputs can replay it
+        let seed = self.next_seed.take().unwrap_or_else(|| rng.randi() as u64);
+        rng.set_seed(seed as i64);
+        self.log_event(Event::GameStarted {
+            seed,
+            players: self.players.len(),
+            demo: self.state == GameState::Demo,
+        });
+        self.run_start_tick = self.tick;
+        self.last_run = None;
+        if self.state != GameState::Demo && !self.telemetry_endpoint.is_empty() {
+            self.run_summary = Some(RunSummary::new(seed));
+        }
+        let rng = self.rng.as_ref().unwrap();
+        let record = !self.replay_record_path.is_empty() || !self.leaderboard_url.is_empty();
+        if self.state != GameState::Demo && record {
+            let fps = Engine::godot_singleton().iterations_per_second() as u32;
+            if let Some(player) = self
+                .players
+                .first()
+                .and_then(|slot| Instance::<Player, _>::from_base(slot.node.clone()))
+            {
+                let mut replay = Replay::new(seed, fps);
+                replay.rules = Rules::Game;
+                unsafe { player.assume_safe() }
+                    .map_mut(|p, _| p.recording = Some(replay))
+                    .ok();
+            }
+        }
+        match self.alien_spawn_timer.as_ref() {
+            Some(timer) => timer.start(rng.randf_range(0.5, 1.0)),
+            None => return,
+        }
+        match self.bgm.as_ref() {
+            Some(bgm) => bgm.play(0.0),
+            None => return,
+        }
+
+        owner.emit_signal("restart_player", &[]);
+    }
+
+    fn end_game(&mut self, owner: &Node) {
+        if !self.change_state(owner, GameState::GameOver) {
+            return;
+        }
+        godot_print!("game end"); // info log
+        self.log_event(Event::GameOver {
+            scores: self.players.iter().map(|slot| slot.score).collect(),
+        });
+        self.finish_run("game_over");
+        self.stop_game();
+        if let Some(replay) = self.take_replay() {
+            self.save_replay(&replay);
+            // the replay does not hold the continues, so those runs are not ranked
+            if self.continue_count == 0 {
+                self.last_run = Some(FinishedRun {
+                    replay,
+                    score: self.players.first().map(|slot| slot.score).unwrap_or(0),
+                    kills: self.beated_alien_num,
+                    seconds: self.run_seconds(),
+                });
+            }
+        }
+        let cause = self
+            .last_death
+            .take()
+            .map(|cause| cause.describe())
+            .unwrap_or_default();
+        owner.emit_signal("game_over", &[Variant::from_str(cause)]);
+    }
+
+    fn set_heat(&mut self, owner: &Node, heat: f32) {
+        self.stage_heat = heat;
+        self.log_event(Event::HeatChanged { heat });
+        if let Some(summary) = self.run_summary.as_mut() {
+            summary.max_heat = summary.max_heat.max(heat);
+        }
+        owner.emit_signal("speed_up", &[Variant::from_f64(heat as f64)]);
+    }
+
+    fn set_player_life(&mut self, owner: &Node, player: usize, val: i32) {
+        self.players[player].life = val;
+        owner.emit_signal(
+            "set_remain",
+            &[
+                Variant::from_i64(val as i64),
+                Variant::from_i64(player as i64),
+            ],
+        );
+    }
+
+    fn set_player_score(&mut self, owner: &Node, player: usize, val: i32) {
+        self.players[player].score = val;
+        owner.emit_signal(
+            "set_score",
+            &[
+                Variant::from_i64(val as i64),
+                Variant::from_i64(player as i64),
+            ],
+        );
+    }
+
+    fn set_beated_alien_num(&mut self, _owner: &Node, val: i32) {
+        self.beated_alien_num = val;
+    }
+
+    fn get_alien_magazine(&self) -> &Magazine<Alien> {
+        self.aliens_magazine.as_ref().unwrap()
+    }
+
+    fn mut_alien_magazine(&mut self) -> &mut Magazine<Alien> {
+        self.aliens_magazine.as_mut().unwrap()
+    }
+
+    fn get_alibul_magazine(&self) -> &Magazine<AlienBullet> {
+        self.alibullets_magazine.as_ref().unwrap()
+    }
+
+    fn mut_alibul_magazine(&mut self) -> &mut Magazine<AlienBullet> {
+        self.alibullets_magazine.as_mut().unwrap()
+    }
+
+    #[export]
+    fn spawn_alien(&mut self, owner: &Node) {
+        // godot_print!("spawn_alien");
+
+        let (pos, process, dir, interval, seed): (Vector3, AlienProcessPattern, Vector3, f64, i64) =
+            match self.rng {
+                Some(ref mut rng) => {
+                    let interval = rng.randf_range(0.5, 3.0) / self.stage_heat as f64;
+                    // aliens draw from the run seed so replays follow the same wave
+                    let seed = rng.randi();
+
+                    let (pos, process, dir) = if rng.randi_range(0, 1) == 0 {
+                        let spawn_x = rng
+                            .randf_range(self.env.left_limit as f64, self.env.right_limit as f64);
+                        (
+                            Vector3::new(spawn_x as f32, 0.0, self.play_field.spawn_top_z),
+                            AlienProcessPattern::Invasion,
+                            Vector3::new(0.0, 0.0, 1.0),
+                        )
+                    } else {
+                        let mid = ((self.env.up_limit + self.env.down_limit) / 2.0) as f64;
+                        let spawn_z = rng.randf_range(self.env.up_limit as f64, mid);
+                        let t = [-1.0, 1.0][rng.randi_range(0, 1) as usize];
+                        let x = t * self.play_field.spawn_side_x;
+                        let dir = Vector3::new(-t, 0.0, 0.0);
+                        (
+                            Vector3::new(x, 0.0, spawn_z as f32),
+                            AlienProcessPattern::Dir,
+                            dir,
+                        )
+                    };
+
+                    (pos, process, dir, interval, seed)
+                }
+                None => return,
+            };
+
+        let field = self.play_field;
+        let id = self.next_alien_id;
+        let kind = self.alien_kind.clone();
+        let aliens_scene = match self.mut_alien_magazine().hammer() {
+            Some(a) => {
+                unsafe { a.assume_safe() }
+                    .map_mut(|t, owner| {
+                        t.set_play_field(&field);
+                        t.id = id;
+                        t.kind = kind;
+                        t.seed_rng(seed);
+                        t.reset(&owner);
+                        t.speed_up(self.stage_heat);
+                        t.set_dir_change_span_random(1.0 / self.stage_heat);
+                        t.set_fire_span_random(1.0 / self.stage_heat);
+                        t.set_dir(dir);
+                        t.set_process(process);
+                        owner.set_translation(pos);
+                    })
+                    .ok();
+                a.into_base()
+            }
+            None => {
+                self.pool_exhausted(owner, "aliens");
+                return;
+            }
+        };
+        self.next_alien_id += 1;
+        self.log_event(Event::AlienSpawned {
+            id,
+            kind: self.alien_kind.clone(),
+            pattern: process.name(),
+            x: pos.x,
+            z: pos.z,
+        });
+
+        // godot_print!("spawn_alien beep");
+
+        // let pos = Vector3::new(spawn_x as f32, 0.0, -27.0);
+        // let pos = Vector3::new(spawn_x as f32, 0.0, -10.0);
+
+        owner.add_child(aliens_scene, false);
+        self.alien_spawn_timer
+            .as_ref()
+            .unwrap()
+            .set_wait_time(interval);
+
+        // godot_print!("spawn_alien_beep");
+    }
+
+    #[export]
+    fn collect_alien(&mut self, _owner: &Node, alien_var: Variant, generation: u64) {
+        // godot_print!("stage collect alien");
+        if let Some(alien_area) = alien_var.try_to_object::<Area>() {
+            self.mut_alien_magazine()
+                .charge_bullet(alien_area, generation);
+        }
+        /*
+        godot_print!(
+            "end stage collect alien: {}",
+            self.get_alien_magazine().get_left_num()
+        );
+        */
+    }
+
+    #[export]
+    fn collect_alien_bullet(&mut self, _owner: &Node, alien_bullet_var: Variant, generation: u64) {
+        // godot_print!("stage collect alien bullet");
+        if let Some(alien_bullet_area) = alien_bullet_var.try_to_object::<Area>() {
+            self.mut_alibul_magazine()
+                .charge_bullet(alien_bullet_area, generation);
+        }
+        /*
+        godot_print!(
+            "end stage collect alien bullet: {}",
+            self.get_alibul_magazine().get_left_num()
+        );
+        */
+    }
+
+    #[export]
+    // `_cause` is the Dictionary form for scripts, the typed cause is read from the Player
+    fn player_beated(&mut self, owner: &Node, player: i64, _cause: Variant) {
+        let player = player as usize;
+        if player >= self.players.len() {
+            return;
+        }
+        let cause = Instance::<Player, _>::from_base(self.players[player].node.clone())
+            .and_then(|p| unsafe { p.assume_safe() }.map(|p, _| p.hit_by.clone()).ok())
+            .unwrap_or_default();
+        godot_print!("Player {}: {}", player + 1, cause.describe()); // info log
+        self.set_player_life(owner, player, self.players[player].life - 1);
+        self.log_event(Event::PlayerHit {
+            player,
+            life: self.players[player].life,
+            cause: cause.clone(),
+        });
+        let at = self.run_seconds();
+        if let Some(summary) = self.run_summary.as_mut() {
+            summary.deaths.push(Death {
+                at,
+                player,
+                cause: cause.clone(),
+            });
+        }
+        self.last_death = Some(cause);
+        if self.players[player].life > 0 {
+            godot_print!("Player Restart: {}", player + 1); // info log
+            unsafe {
+                owner.call_deferred("player_restart", &[Variant::from_i64(player as i64)]);
+            }
+        } else if self.players.iter().all(|slot| slot.life <= 0) {
+            if self.state == GameState::Demo {
+                unsafe {
+                    owner.call_deferred("stop_demo", &[]);
+                }
+            } else if self.credits_left > 0 {
+                self.offer_continue(owner);
+            } else {
+                self.end_game(owner);
+            }
+        }
+    }
+
+    fn offer_continue(&mut self, owner: &Node) {
+        if !self.change_state(owner, GameState::Continue) {
+            return;
+        }
+        // freeze the wave until the player decides
+        Self::set_tree_paused(owner, true);
+        self.continue_left = self.continue_time;
+        self.emit_continue_offered(owner);
+        if let Some(timer) = self.continue_timer.as_ref() {
+            timer.start(0.0);
+        }
+    }
+
+    fn emit_continue_offered(&self, owner: &Node) {
+        owner.emit_signal(
+            "continue_offered",
+            &[
+                Variant::from_i64(self.continue_left as i64),
+                Variant::from_i64(self.credits_left as i64),
+            ],
+        );
+    }
+
+    #[export]
+    fn continue_tick(&mut self, owner: &Node) {
+        if self.state != GameState::Continue {
+            return;
+        }
+        self.continue_left -= 1;
+        self.emit_continue_offered(owner);
+        if self.continue_left <= 0 {
+            self.decline_continue(owner);
+        }
+    }
+
+    #[export]
+    fn take_continue(&mut self, owner: &Node) {
+        if self.state != GameState::Continue
+            || self.credits_left <= 0
+            || !self.change_state(owner, GameState::Playing)
+        {
+            return;
+        }
+        if let Some(timer) = self.continue_timer.as_ref() {
+            timer.stop();
+        }
+        self.credits_left -= 1;
+        self.continue_count += 1;
+        godot_print!("Continue: {}", self.continue_count); // info log
+        self.log_event(Event::ContinueTaken {
+            count: self.continue_count,
+        });
+
+        for i in 0..self.players.len() {
+            self.set_player_life(owner, i, self.player_life);
+            if self.continue_resets_score {
+                self.set_player_score(owner, i, 0);
+            }
+        }
+        // the wave and the stage heat are kept as they are
+        Self::set_tree_paused(owner, false);
+        owner.emit_signal("restart_player", &[]);
+        owner.emit_signal(
+            "continue_taken",
+            &[Variant::from_i64(self.continue_count as i64)],
+        );
+    }
+
+    #[export]
+    fn decline_continue(&mut self, owner: &Node) {
+        if self.state != GameState::Continue {
+            return;
+        }
+        if let Some(timer) = self.continue_timer.as_ref() {
+            timer.stop();
+        }
+        Self::set_tree_paused(owner, false);
+        self.end_game(owner);
+    }
+
+    #[export]
+    fn player_crashed(&mut self, owner: &Node, player: i64) {
+        if let Some(slot) = self.players.get_mut(player as usize) {
+            slot.alive = false;
+        }
+        if self.state == GameState::Playing && self.players.iter().all(|slot| !slot.alive) {
+            self.change_state(owner, GameState::PlayerDying);
+        }
+    }
+
+    #[export]
+    fn player_respawned(&mut self, owner: &Node, player: i64) {
+        if let Some(slot) = self.players.get_mut(player as usize) {
+            slot.alive = true;
+        }
+        if self.state == GameState::PlayerDying {
+            self.change_state(owner, GameState::Playing);
+        }
+    }
+
+    #[export]
+    fn player_restart(&mut self, _owner: &Node, player: i64) {
+        if let Some(slot) = self.players.get(player as usize) {
+            unsafe {
+                slot.node.assume_safe().call("reset", &[]);
+            }
+        }
+    }
+
+    #[export]
+    fn alien_beated(&mut self, owner: &Node, player: i64, pos: Vector3) {
+        // self.beated_alien_num += 1;
+        self.set_beated_alien_num(owner, self.beated_alien_num + 1);
+        if self.beated_alien_num % 10 == 0 {
+            // self.stage_heat *= 1.1;
+            // owner.emit_signal("speed_up", &[Variant::from_f64(self.stage_heat as f64)]);
+            self.set_heat(owner, self.stage_heat * 1.1);
+            godot_print!("Stage Heat: {}", self.stage_heat); // info log
+        }
+
+        let player = player as usize;
+        self.log_event(Event::AlienKilled {
+            player,
+            x: pos.x,
+            z: pos.z,
+        });
+        if player >= self.players.len() {
+            return;
+        }
+        self.set_player_score(owner, player, self.players[player].score + 1);
+        if self.players[player].score % POWER_UP_SPAN == 0 {
+            unsafe {
+                self.players[player]
+                    .node
+                    .assume_safe()
+                    .call("power_up", &[]);
+            }
+        }
+        if self.players[player].score % 20 == 0 && self.players[player].life > 0 {
+            // self.player_life += 1;
+            self.set_player_life(owner, player, self.players[player].life + 1);
+            if let Some(sound) = self.extend_sound.as_ref() {
+                sound.play(0.0);
+            }
+            godot_print!(
+                "Player Life Extended: {} {}",
+                player + 1,
+                self.players[player].life
+            ); // info log
+            self.log_event(Event::Extend {
+                player,
+                life: self.players[player].life,
+            });
+        }
+        // godot_print!("beated_alien_num: {}", self.beated_alien_num);
+    }
+
+    #[export]
+    fn alien_fire(
+        &mut self,
+        owner: &Node,
+        pos: Variant,
+        dir: Variant,
+        speed: Variant,
+        kind: Variant,
+        alien_id: Variant,
+        alien_kind: Variant,
+    ) {
+        let pos: Vector3 = pos.to_vector3();
+        let dir: Vector3 = dir.to_vector3();
+        let speed: f64 = speed.to_f64();
+        let kind: i64 = kind.to_i64();
+
+        let dirs = barrage_dirs(kind.max(0) as usize, dir);
+        let origin = BulletOrigin {
+            barrage: kind.max(0) as usize,
+            volley: self.next_volley,
+            spawn_tick: self.tick,
+            alien_kind: alien_kind.to_godot_string().to_string(),
+            alien_id: alien_id.to_i64(),
+        };
+        self.next_volley += 1;
+
+        // godot_print!("alien_fire: {:?} {:?} {:?} {:?}", pos, dir, speed, kind);
+
+        if let Some(field) = self.bullet_field.as_ref() {
+            let fired = unsafe { field.assume_safe() }
+                .map_mut(|f, _| {
+                    dirs.into_iter()
+                        .all(|dir| f.push(pos, dir, speed as f32, origin.clone()))
+                })
+                .unwrap_or(false);
+            if !fired {
+                // the rest of the barrage is dropped
+                self.pool_exhausted(owner, "alien_bullets");
+            }
+            return;
+        }
+
+        for dir in dirs {
+            let area_collision = !self.rust_collision;
+            let field = self.play_field;
+            let bullet_scene = match self.mut_alibul_magazine().hammer() {
+                Some(a) => {
+                    unsafe { a.assume_safe() }
+                        .map_mut(|t, owner| {
+                            t.set_play_field(&field);
+                            owner.set_monitoring(area_collision);
+                            owner.set_monitorable(area_collision);
+                            owner.set_translation(pos);
+                            t.set_speed(speed as f32);
+                            // godot_print!("dir: {:?}", dir);
+                            t.set_dir(dir);
+                            t.origin = Some(origin.clone());
+                            t.flying = true;
+                        })
+                        .ok();
+                    a.into_base()
+                }
+                None => {
+                    // the rest of the barrage is dropped
+                    self.pool_exhausted(owner, "alien_bullets");
+                    return;
+                }
+            };
+            owner.add_child(bullet_scene, false);
+        }
+    }
+}
+
+fn load_replay(path: &str) -> Result<Replay, String> {
+    let file = File::new();
+    if file.open(path, File::READ).is_err() {
+        return Err(format!("failed to load replay {}", path));
+    }
+    let text = file.get_as_text().to_string();
+    file.close();
+    Replay::parse(&text).map_err(|e| format!("{} in {}", e, path))
+}
+
+fn circles(areas: &[Ref<Area, Shared>], r: f32) -> Vec<Circle> {
+    areas
+        .iter()
+        .map(|a| {
+            let pos = unsafe { a.assume_safe() }.global_transform().origin;
+            Circle::new(pos.x, pos.z, r)
+        })
+        .collect()
+}
+
+// deferred like Area signals, the handlers may emit signals back to the Stage
+fn dispatch_hit(
+    bullet: &Ref<Area, Shared>,
+    bullet_method: &str,
+    target: &Ref<Area, Shared>,
+    target_method: &str,
+) {
+    unsafe {
+        bullet
+            .assume_safe()
+            .call_deferred(bullet_method, &[target.to_variant()]);
+        target
+            .assume_safe()
+            .call_deferred(target_method, &[bullet.to_variant()]);
+    }
+}
+
+struct PlayerSlot {
+    node: Ref<Area, Shared>,
+    life: i32,
+    score: i32,
+    alive: bool,
+}
+
+impl PlayerSlot {
+    fn new(node: Ref<Area, Shared>) -> Self {
+        PlayerSlot {
+            node,
+            life: 0,
+            score: 0,
+            alive: false,
+        }
+    }
+}
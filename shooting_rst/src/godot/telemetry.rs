@@ -0,0 +1,185 @@
+use gdnative::api::{Directory, File, HTTPClient, HTTPRequest};
+use gdnative::prelude::*;
+
+use crate::telemetry::{batch, sending_path, Batch, RunSummary};
+
+/// Sends the queued run summaries through `HTTPRequest`, so the wasm build sends them too.
+/// One batch is in flight at a time, a failed one stays queued for the next run.
+/// The queue is kept with Godot's `File`, on `user://` it persists in the browser as well.
+#[derive(NativeClass)]
+#[inherit(Node)]
+pub struct Telemetry {
+    // run summaries are POSTed here
+    #[property]
+    pub endpoint: String,
+    // summaries not sent yet
+    #[property]
+    pub queue_path: String,
+
+    http: Option<Ref<HTTPRequest, Shared>>,
+    // summaries in the batch in flight
+    in_flight: Option<usize>,
+}
+
+#[gdnative::methods]
+impl Telemetry {
+    fn new(_owner: &Node) -> Self {
+        Telemetry {
+            endpoint: String::new(),
+            queue_path: "user://telemetry.jsonl".to_string(),
+
+            http: None,
+            in_flight: None,
+        }
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Node) {
+        // a run may end on a paused tree
+        owner.set_pause_mode(Node::PAUSE_MODE_PROCESS);
+        let http = HTTPRequest::new();
+        http.connect(
+            "request_completed",
+            unsafe { owner.assume_shared() },
+            "_on_request_completed",
+            VariantArray::new_shared(),
+            0,
+        )
+        .unwrap();
+        let http = http.into_shared();
+        owner.add_child(http.clone(), false);
+        self.http = Some(http);
+        // whatever an earlier session could not send
+        self.send();
+    }
+
+    /// Queues the summary of a finished run and sends the queue.
+    pub fn queue(&mut self, summary: &RunSummary) {
+        if let Err(e) = self.push(summary) {
+            godot_error!("failed to queue telemetry: {}", e);
+            return;
+        }
+        self.send();
+    }
+
+    // appends a line to the queue file
+    fn push(&self, summary: &RunSummary) -> Result<(), String> {
+        let path = self.queue_path.as_str();
+        let file = File::new();
+        let mode = if file.file_exists(path) {
+            File::READ_WRITE
+        } else {
+            File::WRITE
+        };
+        if file.open(path, mode).is_err() {
+            return Err(format!("cannot open {}", path));
+        }
+        file.seek_end(0);
+        file.store_line(summary.to_json());
+        file.close();
+        Ok(())
+    }
+
+    // moves the queue aside to send it, unless a batch is still waiting there
+    fn next_batch(&self) -> Result<Option<Batch>, String> {
+        let path = self.queue_path.as_str();
+        let sending = sending_path(path);
+        let dir = Directory::new();
+        loop {
+            if !dir.file_exists(sending.as_str()) {
+                if !dir.file_exists(path) {
+                    return Ok(None);
+                }
+                if dir.rename(path, sending.as_str()).is_err() {
+                    return Err(format!("cannot move {} to {}", path, sending));
+                }
+            }
+            let file = File::new();
+            if file.open(sending.as_str(), File::READ).is_err() {
+                return Err(format!("cannot open {}", sending));
+            }
+            let text = file.get_as_text().to_string();
+            file.close();
+            if let Some(batch) = batch(&text) {
+                return Ok(Some(batch));
+            }
+            if dir.remove(sending.as_str()).is_err() {
+                return Err(format!("cannot remove {}", sending));
+            }
+        }
+    }
+
+    // drops the batch the endpoint has taken
+    fn sent(&self) -> Result<(), String> {
+        let sending = sending_path(&self.queue_path);
+        let dir = Directory::new();
+        if dir.file_exists(sending.as_str()) && dir.remove(sending.as_str()).is_err() {
+            return Err(format!("cannot remove {}", sending));
+        }
+        Ok(())
+    }
+
+    fn send(&mut self) {
+        if self.in_flight.is_some() || self.endpoint.is_empty() {
+            return;
+        }
+        let http = match self.http.as_ref() {
+            Some(http) => unsafe { http.assume_safe() },
+            None => return,
+        };
+        let batch = match self.next_batch() {
+            Ok(Some(batch)) => batch,
+            Ok(None) => return,
+            Err(e) => {
+                godot_error!("failed to read telemetry: {}", e);
+                return;
+            }
+        };
+        let mut headers = StringArray::new();
+        headers.push("Content-Type: application/json".into());
+        match http.request(
+            self.endpoint.as_str(),
+            headers,
+            true,
+            HTTPClient::METHOD_POST,
+            batch.body.as_str(),
+        ) {
+            Ok(()) => self.in_flight = Some(batch.runs),
+            Err(e) => godot_warn!("telemetry kept for later: {:?}", e),
+        }
+    }
+
+    #[export]
+    fn _on_request_completed(
+        &mut self,
+        _owner: &Node,
+        result: i64,
+        code: i64,
+        _headers: StringArray,
+        _body: ByteArray,
+    ) {
+        let runs = match self.in_flight.take() {
+            Some(runs) => runs,
+            None => return,
+        };
+        if result != HTTPRequest::RESULT_SUCCESS {
+            godot_warn!(
+                "telemetry kept for later: request failed with result {}",
+                result
+            );
+            return;
+        }
+        if !(200..300).contains(&code) {
+            godot_warn!("telemetry kept for later: endpoint answered {}", code);
+            return;
+        }
+        if let Err(e) = self.sent() {
+            godot_error!("failed to drop sent telemetry: {}", e);
+            return;
+        }
+        godot_print!("telemetry: sent {} runs", runs); // info log
+
+        // summaries queued while this batch was out
+        self.send();
+    }
+}
@@ -0,0 +1,119 @@
+//! Plain HTTP/1.1 over std, enough for the local development servers of the sim feature.
+//!
+//! The game itself sends telemetry and talks to the leaderboard through Godot's
+//! `HTTPRequest`, which also works where std sockets and threads do not.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+// bodies larger than this are refused by `read_request`
+const MAX_BODY: usize = 1 << 20;
+
+/// A request as the development servers see it.
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    /// without the query
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Reads one request. Query values are taken as they are, without percent decoding.
+pub fn read_request(stream: &TcpStream) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .map_err(|e| e.to_string())?;
+    let mut words = request_line.split_whitespace();
+    let (method, target) = match (words.next(), words.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => return Err("bad request line".to_string()),
+    };
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().map_err(|_| "bad Content-Length")?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err("body too large".to_string());
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    let body = String::from_utf8(body).map_err(|_| "body is not utf-8")?;
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), query),
+        None => (target, ""),
+    };
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => (k.to_string(), v.to_string()),
+            None => (pair.to_string(), String::new()),
+        })
+        .collect();
+    Ok(Request {
+        method,
+        path,
+        query,
+        body,
+    })
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        422 => "Unprocessable Entity",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+/// Answers with a JSON body, empty for 204.
+/// The CORS headers also answer the preflight a browser sends before a JSON POST.
+pub fn respond(mut stream: &TcpStream, status: u16, body: &str) {
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Content-Type\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    );
+}
+
+/// `{"error": message}`
+pub fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
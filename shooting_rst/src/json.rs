@@ -0,0 +1,34 @@
+//! Values for the JSON the events and telemetry write by hand, escaped by serde_json.
+
+/// A quoted and escaped JSON string.
+pub fn string(s: &str) -> String {
+    serde_json::Value::from(s).to_string()
+}
+
+// JSON has no NaN or infinity
+pub fn float(v: f32) -> String {
+    if v.is_finite() {
+        v.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_round_trips() {
+        let text = "name \"quoted\" \\ \n\t\u{1} 😀";
+        let parsed: String = serde_json::from_str(&string(text)).unwrap();
+        assert_eq!(parsed, text);
+    }
+
+    #[test]
+    fn floats_are_numbers_or_null() {
+        assert_eq!(float(0.5), "0.5");
+        assert_eq!(float(f32::NAN), "null");
+        assert_eq!(float(f32::INFINITY), "null");
+    }
+}
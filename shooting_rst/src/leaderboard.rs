@@ -0,0 +1,350 @@
+//! Payloads of the online leaderboard, built and checked the same way by the game and the mock.
+//!
+//! ```text
+//! POST {base}/scores                    Submission   -> 201 {"id": 12, "rank": 3}
+//!                                                     -> 202 {"id": 13, "rank": null}
+//! GET  {base}/scores/top?limit=N                     -> {"standings": [Standing, ..]}
+//! GET  {base}/scores/around/ID?span=N                -> {"standings": [Standing, ..]}
+//! ```
+//!
+//! A submission whose replay the board cannot re-run is held without a rank (202) and
+//! stays off the standings. Failures answer with `{"error": "..."}`.
+//! Seeds and replay hashes are strings, JSON numbers lose digits past 2^53.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::replay::Replay;
+
+pub const MAX_NAME_CHARS: usize = 12;
+/// most standings one fetch may ask for
+pub const MAX_LIMIT: u32 = 100;
+pub const SUBMIT_PATH: &str = "/scores";
+
+pub fn top_path(limit: u32) -> String {
+    format!("/scores/top?limit={}", limit.clamp(1, MAX_LIMIT))
+}
+
+/// `span` standings above and below entry `id`.
+pub fn around_path(id: u64, span: u32) -> String {
+    format!("/scores/around/{}?span={}", id, span.min(MAX_LIMIT / 2))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardError {
+    pub message: String,
+}
+
+impl LeaderboardError {
+    fn new(message: impl Into<String>) -> Self {
+        LeaderboardError {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LeaderboardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "leaderboard: {}", self.message)
+    }
+}
+
+impl From<serde_json::Error> for LeaderboardError {
+    fn from(e: serde_json::Error) -> Self {
+        LeaderboardError::new(e.to_string())
+    }
+}
+
+pub fn check_name(name: &str) -> Result<(), LeaderboardError> {
+    let chars = name.chars().count();
+    if chars == 0 || chars > MAX_NAME_CHARS {
+        return Err(LeaderboardError::new(format!(
+            "names are 1 to {} characters",
+            MAX_NAME_CHARS
+        )));
+    }
+    if name.trim() != name || name.chars().any(char::is_control) {
+        return Err(LeaderboardError::new(
+            "names have no control characters or edge spaces",
+        ));
+    }
+    Ok(())
+}
+
+// seeds as decimal strings
+mod seed {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(seed)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(|_| D::Error::custom("seed is not a decimal string"))
+    }
+}
+
+// replay hashes as 16 hex digits
+mod hash {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(hash: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{:016x}", hash))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let hash = String::deserialize(deserializer)?;
+        if hash.len() != 16 {
+            return Err(D::Error::custom("replay_hash is 16 hex digits"));
+        }
+        u64::from_str_radix(&hash, 16).map_err(|_| D::Error::custom("replay_hash is not hex"))
+    }
+}
+
+/// A finished run of the first player, with the replay that backs it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Submission {
+    pub name: String,
+    pub score: i32,
+    pub kills: i32,
+    pub seconds: f32,
+    #[serde(with = "seed")]
+    pub seed: u64,
+    #[serde(with = "hash")]
+    pub replay_hash: u64,
+    /// text form of the replay, for a verifier to run
+    pub replay: String,
+}
+
+impl Submission {
+    pub fn new(name: &str, score: i32, kills: i32, seconds: f32, replay: &Replay) -> Self {
+        Submission {
+            name: name.to_string(),
+            score,
+            kills,
+            seconds,
+            seed: replay.seed,
+            replay_hash: replay.hash(),
+            replay: replay.to_string(),
+        }
+    }
+
+    /// Checks what can be checked without running the replay.
+    pub fn validate(&self) -> Result<(), LeaderboardError> {
+        check_name(&self.name)?;
+        if self.score < 0 || self.kills < 0 {
+            return Err(LeaderboardError::new("score and kills are not negative"));
+        }
+        if !self.seconds.is_finite() || self.seconds < 0.0 {
+            return Err(LeaderboardError::new("seconds is not a duration"));
+        }
+        let replay =
+            Replay::parse(&self.replay).map_err(|e| LeaderboardError::new(e.to_string()))?;
+        if replay.seed != self.seed {
+            return Err(LeaderboardError::new("seed differs from the replay"));
+        }
+        if replay.hash() != self.replay_hash {
+            return Err(LeaderboardError::new("replay_hash differs from the replay"));
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        to_json(self)
+    }
+
+    /// Parses and validates a submission body.
+    pub fn from_json(body: &str) -> Result<Self, LeaderboardError> {
+        let submission: Submission = serde_json::from_str(body)?;
+        submission.validate()?;
+        Ok(submission)
+    }
+}
+
+/// One line of the board.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    pub rank: u32,
+    pub id: u64,
+    pub name: String,
+    pub score: i32,
+    #[serde(with = "seed")]
+    pub seed: u64,
+    #[serde(with = "hash")]
+    pub replay_hash: u64,
+}
+
+impl Standing {
+    pub fn to_json(&self) -> String {
+        to_json(self)
+    }
+
+    fn validate(&self) -> Result<(), LeaderboardError> {
+        if self.rank < 1 {
+            return Err(LeaderboardError::new("ranks start at 1"));
+        }
+        check_name(&self.name)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Standings<T> {
+    standings: T,
+}
+
+/// `{"standings": [..]}`
+pub fn standings_json(standings: &[Standing]) -> String {
+    to_json(&Standings { standings })
+}
+
+/// Parses a top or around answer, refusing boards that are out of order.
+pub fn parse_standings(body: &str) -> Result<Vec<Standing>, LeaderboardError> {
+    let Standings { standings } = serde_json::from_str::<Standings<Vec<Standing>>>(body)?;
+    for standing in standings.iter() {
+        standing.validate()?;
+    }
+    for pair in standings.windows(2) {
+        if pair[1].rank <= pair[0].rank || pair[1].score > pair[0].score {
+            return Err(LeaderboardError::new("standings are out of order"));
+        }
+    }
+    Ok(standings)
+}
+
+/// The answer to a submission.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub id: u64,
+    /// `None` while the entry is held, unranked, for its replay to be checked
+    // a plain Option field could be left out, the answer says null
+    #[serde(deserialize_with = "Option::deserialize")]
+    pub rank: Option<u32>,
+}
+
+impl Receipt {
+    pub fn to_json(&self) -> String {
+        to_json(self)
+    }
+
+    pub fn parse(body: &str) -> Result<Self, LeaderboardError> {
+        let receipt: Receipt = serde_json::from_str(body)?;
+        if receipt.rank == Some(0) {
+            return Err(LeaderboardError::new("ranks start at 1"));
+        }
+        Ok(receipt)
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// The message of an `{"error": ..}` answer, when there is one.
+pub fn parse_error(body: &str) -> Option<String> {
+    serde_json::from_str::<ErrorBody>(body)
+        .ok()
+        .map(|e| e.error)
+}
+
+// the payloads have string keys and no maps, writing them cannot fail
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("leaderboard payloads serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::ReplayInput;
+
+    fn submission() -> Submission {
+        let mut replay = Replay::new(u64::MAX, 60);
+        for _ in 0..3 {
+            replay.push(ReplayInput::default()).unwrap();
+        }
+        Submission::new("ace", 1200, 12, 0.05, &replay)
+    }
+
+    #[test]
+    fn submission_round_trips() {
+        let s = submission();
+        assert_eq!(Submission::from_json(&s.to_json()), Ok(s));
+    }
+
+    #[test]
+    fn checks_names() {
+        assert!(check_name("ace").is_ok());
+        assert!(check_name("エース").is_ok());
+        assert!(check_name("").is_err());
+        assert!(check_name(&"a".repeat(MAX_NAME_CHARS + 1)).is_err());
+        assert!(check_name(" ace").is_err());
+        assert!(check_name("a\u{7}ce").is_err());
+    }
+
+    #[test]
+    fn submissions_match_their_replay() {
+        let bad = |f: fn(&mut Submission)| {
+            let mut s = submission();
+            f(&mut s);
+            s.validate().is_err() && Submission::from_json(&s.to_json()).is_err()
+        };
+        assert!(bad(|s| s.score = -1));
+        assert!(bad(|s| s.kills = -1));
+        assert!(bad(|s| s.seconds = -1.0));
+        assert!(bad(|s| s.seed = 1));
+        assert!(bad(|s| s.replay_hash ^= 1));
+        assert!(bad(|s| s.replay = "not a replay".to_string()));
+    }
+
+    #[test]
+    fn submission_fields_are_typed() {
+        let json = submission().to_json();
+        let changed = |from: &str, to: &str| Submission::from_json(&json.replacen(from, to, 1));
+        assert!(changed(r#""score":1200"#, r#""score":"1200""#).is_err());
+        assert!(changed(r#""score":1200"#, r#""score":1200.5"#).is_err());
+        assert!(changed(r#""score":1200"#, r#""score":3000000000"#).is_err());
+        assert!(changed(r#""seed":"18446744073709551615""#, r#""seed":1"#).is_err());
+        assert!(changed(r#","kills":12"#, "").is_err());
+        assert!(Submission::from_json("[]").is_err());
+    }
+
+    #[test]
+    fn standings_are_in_order() {
+        let standing = |rank: u32, score: i32| Standing {
+            rank,
+            id: rank as u64,
+            name: "ace".to_string(),
+            score,
+            seed: u64::MAX,
+            replay_hash: 0x0123_4567_89ab_cdef,
+        };
+        let board = vec![standing(1, 300), standing(2, 300), standing(3, 100)];
+        assert_eq!(parse_standings(&standings_json(&board)), Ok(board));
+        assert!(parse_standings(&standings_json(&[standing(2, 100), standing(1, 300)])).is_err());
+        assert!(parse_standings(&standings_json(&[standing(1, 100), standing(2, 300)])).is_err());
+        assert!(parse_standings(&standings_json(&[standing(0, 100)])).is_err());
+    }
+
+    #[test]
+    fn receipts_and_errors_parse() {
+        let receipt = Receipt {
+            id: 0,
+            rank: Some(1),
+        };
+        assert_eq!(Receipt::parse(&receipt.to_json()), Ok(receipt));
+        let held = Receipt { id: 1, rank: None };
+        assert_eq!(Receipt::parse(&held.to_json()), Ok(held));
+        assert!(Receipt::parse(r#"{"id":1}"#).is_err());
+        assert!(Receipt::parse(r#"{"id":1,"rank":0}"#).is_err());
+        assert!(Receipt::parse(r#"{"id":-1,"rank":1}"#).is_err());
+        assert_eq!(
+            parse_error(r#"{"error":"try later"}"#).as_deref(),
+            Some("try later")
+        );
+        assert_eq!(parse_error("<html>"), None);
+    }
+}
@@ -0,0 +1,324 @@
+//! Per physics frame input of one player with the seed of the run, for demos and score checks.
+//!
+//! Stored as text, a header then one line per run of frames holding the same input:
+//!
+//! ```text
+//! shooting_rst replay 1
+//! seed 42
+//! fps 60
+//! 120 0 0 1 0
+//! 30 -1 0.5 1 1
+//! ```
+//!
+//! The columns of a run are frames, stick x, stick z, shoot and focus.
+//! Replays recorded by the Godot game have a `rules game` line after `fps`, see `Rules`.
+
+use std::fmt;
+
+const MAGIC: &str = "shooting_rst replay 1";
+
+/// Longest replay, four hours at 60 fps. Verifying steps every frame, so parsing stops here.
+pub const MAX_FRAMES: u32 = 4 * 60 * 60 * 60;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReplayInput {
+    pub x: f32,
+    pub z: f32,
+    pub shoot: bool,
+    pub focus: bool,
+}
+
+/// Whose rules a replay was recorded under. Only the sim core's can be re-run by `sim::verify`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rules {
+    /// `sim::World`, also assumed when a replay has no rules line
+    Sim,
+    /// the Godot Stage, which does not run on the sim core yet
+    Game,
+}
+
+impl Rules {
+    pub fn name(self) -> &'static str {
+        match self {
+            Rules::Sim => "sim",
+            Rules::Game => "game",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sim" => Some(Rules::Sim),
+            "game" => Some(Rules::Game),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub fps: u32,
+    pub rules: Rules,
+    // (end frame exclusive, input), ends are increasing
+    runs: Vec<(u32, ReplayInput)>,
+}
+
+#[derive(Debug)]
+pub struct ReplayError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "replay line {}: {}", self.line, self.message)
+    }
+}
+
+impl Replay {
+    pub fn new(seed: u64, fps: u32) -> Self {
+        Replay {
+            seed,
+            fps,
+            rules: Rules::Sim,
+            runs: Vec::new(),
+        }
+    }
+
+    /// Appends the input of the next frame, fails past `MAX_FRAMES`.
+    pub fn push(&mut self, input: ReplayInput) -> Result<(), ReplayError> {
+        self.push_run(input, 1, 0)
+    }
+
+    // `line` is reported in the error
+    fn push_run(
+        &mut self,
+        input: ReplayInput,
+        frames: u32,
+        line: usize,
+    ) -> Result<(), ReplayError> {
+        if frames == 0 {
+            return Ok(());
+        }
+        let end = self
+            .len()
+            .checked_add(frames)
+            .filter(|&end| end <= MAX_FRAMES)
+            .ok_or_else(|| ReplayError {
+                line,
+                message: format!("longer than {} frames", MAX_FRAMES),
+            })?;
+        match self.runs.last_mut() {
+            Some((last_end, last)) if *last == input => *last_end = end,
+            _ => self.runs.push((end, input)),
+        }
+        Ok(())
+    }
+
+    /// Frames recorded.
+    pub fn len(&self) -> u32 {
+        self.runs.last().map(|&(end, _)| end).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    pub fn seconds(&self) -> f32 {
+        self.len() as f32 / self.fps.max(1) as f32
+    }
+
+    /// `None` past the end.
+    pub fn input(&self, frame: u32) -> Option<ReplayInput> {
+        let i = self.runs.partition_point(|&(end, _)| end <= frame);
+        self.runs.get(i).map(|&(_, input)| input)
+    }
+
+    /// Every frame in order.
+    pub fn inputs(&self) -> impl Iterator<Item = ReplayInput> + '_ {
+        let mut start = 0;
+        self.runs.iter().flat_map(move |&(end, input)| {
+            let frames = start..end;
+            start = end;
+            frames.map(move |_| input)
+        })
+    }
+
+    /// FNV-1a of the text form, to tell replays apart without sending them.
+    pub fn hash(&self) -> u64 {
+        self.to_string().bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ReplayError> {
+        let err = |line: usize, message: &str| ReplayError {
+            line,
+            message: message.to_string(),
+        };
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty())
+            .peekable();
+
+        match lines.next() {
+            Some((_, l)) if l == MAGIC => {}
+            Some((n, _)) => return Err(err(n, "not a replay")),
+            None => return Err(err(0, "empty")),
+        }
+        let mut header = |key: &str| -> Result<u64, ReplayError> {
+            let (n, l) = lines.next().ok_or_else(|| err(0, "header is cut off"))?;
+            let mut words = l.split_whitespace();
+            if words.next() != Some(key) {
+                return Err(err(n, &format!("expected {}", key)));
+            }
+            words
+                .next()
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| err(n, &format!("bad {}", key)))
+        };
+        let seed = header("seed")?;
+        let fps = header("fps")? as u32;
+
+        let mut replay = Replay::new(seed, fps);
+        if let Some(&(n, l)) = lines.peek() {
+            if let Some(name) = l.strip_prefix("rules") {
+                replay.rules = Rules::from_name(name.trim()).ok_or_else(|| err(n, "bad rules"))?;
+                lines.next();
+            }
+        }
+        for (n, l) in lines {
+            let cols: Vec<_> = l.split_whitespace().collect();
+            if cols.len() != 5 {
+                return Err(err(n, "expected frames, x, z, shoot and focus"));
+            }
+            let frames: u32 = cols[0].parse().map_err(|_| err(n, "bad frames"))?;
+            let axis = |s: &str| -> Result<f32, ReplayError> {
+                match s.parse::<f32>() {
+                    Ok(v) if (-1.0..=1.0).contains(&v) => Ok(v),
+                    _ => Err(err(n, "stick out of -1..1")),
+                }
+            };
+            let flag = |s: &str| match s {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                _ => Err(err(n, "flags are 0 or 1")),
+            };
+            let input = ReplayInput {
+                x: axis(cols[1])?,
+                z: axis(cols[2])?,
+                shoot: flag(cols[3])?,
+                focus: flag(cols[4])?,
+            };
+            replay.push_run(input, frames, n)?;
+        }
+        Ok(replay)
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", MAGIC)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "fps {}", self.fps)?;
+        // left out for the sim, so its replays and their hashes read as before
+        if self.rules != Rules::Sim {
+            writeln!(f, "rules {}", self.rules.name())?;
+        }
+        let mut start = 0;
+        for &(end, i) in self.runs.iter() {
+            writeln!(
+                f,
+                "{} {} {} {} {}",
+                end - start,
+                i.x,
+                i.z,
+                i.shoot as u8,
+                i.focus as u8
+            )?;
+            start = end;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(x: f32, z: f32, shoot: bool, focus: bool) -> ReplayInput {
+        ReplayInput { x, z, shoot, focus }
+    }
+
+    // a replay file with `runs` after the header
+    fn text(runs: &str) -> String {
+        format!("{}\nseed 1\nfps 60\n{}", MAGIC, runs)
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let mut replay = Replay::new(u64::MAX, 60);
+        let inputs = [
+            input(0.0, 0.0, true, false),
+            input(0.0, 0.0, true, false),
+            input(-1.0, 0.5, true, true),
+            input(0.123_456_79, -0.987_654_3, false, false),
+        ];
+        for &i in inputs.iter() {
+            replay.push(i).unwrap();
+        }
+        let text = replay.to_string();
+        assert_eq!(text.lines().count(), 3 + 3);
+        let parsed = Replay::parse(&text).unwrap();
+        assert_eq!(parsed, replay);
+        assert_eq!(parsed.inputs().collect::<Vec<_>>(), inputs.to_vec());
+        assert_eq!(parsed.hash(), replay.hash());
+    }
+
+    #[test]
+    fn rules_round_trip() {
+        assert_eq!(Replay::parse(&text("")).unwrap().rules, Rules::Sim);
+        let mut replay = Replay::new(1, 60);
+        assert!(!replay.to_string().contains("rules"));
+        replay.rules = Rules::Game;
+        replay.push(ReplayInput::default()).unwrap();
+        let parsed = Replay::parse(&replay.to_string()).unwrap();
+        assert_eq!(parsed, replay);
+        assert_eq!(Replay::parse(&text("rules nes\n")).unwrap_err().line, 4);
+    }
+
+    #[test]
+    fn input_looks_up_runs() {
+        let replay = Replay::parse(&text("2 0 0 1 0\n3 1 0 0 0\n")).unwrap();
+        assert_eq!(replay.len(), 5);
+        assert_eq!(replay.input(1), Some(input(0.0, 0.0, true, false)));
+        assert_eq!(replay.input(2), Some(input(1.0, 0.0, false, false)));
+        assert_eq!(replay.input(5), None);
+    }
+
+    #[test]
+    fn parse_reports_the_bad_line() {
+        let bad = |run: &str| {
+            Replay::parse(&text(&format!("1 0 0 0 0\n{}\n", run)))
+                .unwrap_err()
+                .line
+        };
+        assert_eq!(bad("1 2 0 0 0"), 5);
+        assert_eq!(bad("1 0 0 2 0"), 5);
+        assert_eq!(bad("1 0 0 0"), 5);
+        assert_eq!(bad("-1 0 0 0 0"), 5);
+        assert!(Replay::parse("seed 1\nfps 60\n").is_err());
+    }
+
+    #[test]
+    fn frames_are_capped() {
+        let overflow = text(&format!("{} 0 0 0 0\n{} 1 0 0 0\n", u32::MAX, u32::MAX));
+        assert_eq!(Replay::parse(&overflow).unwrap_err().line, 4);
+
+        let mut replay = Replay::parse(&text(&format!("{} 0 0 0 0\n", MAX_FRAMES))).unwrap();
+        assert!(replay.push(ReplayInput::default()).is_err());
+        assert_eq!(replay.len(), MAX_FRAMES);
+    }
+}
@@ -0,0 +1,414 @@
+//! Bullet density and dodgeability of barrages, moved with `barrage::Shot` like `AlienBullet`.
+//!
+//! The player area is cut into cells the player crosses in `frames_per_step` frames.
+//! A cell is free when a hitbox somewhere in it misses every bullet, and the player may step
+//! to any of the 8 neighbours, so the player is modelled a little better than it is.
+//! A volley with no path through it under that model is undodgeable for a real player too.
+
+use serde::{Deserialize, Serialize};
+
+use super::level::Level;
+use crate::barrage::{volley, Shot, BULLET_SPEED_RATIO};
+use crate::collision::{Circle, SpatialHash};
+
+/// One volley of a wave, `aim` is a direction.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Emit {
+    pub at: f32,
+    pub from: (f32, f32),
+    pub aim: (f32, f32),
+    pub kind: usize,
+}
+
+/// `volleys` of barrage `kind` fired every `interval` seconds from one place.
+pub fn barrage_wave(
+    kind: usize,
+    from: (f32, f32),
+    aim: (f32, f32),
+    volleys: u32,
+    interval: f32,
+) -> Vec<Emit> {
+    (0..volleys)
+        .map(|i| Emit {
+            at: i as f32 * interval,
+            from,
+            aim,
+            kind,
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StepStats {
+    pub time: f32,
+    /// bullets inside the player area
+    pub bullets: usize,
+    /// most bullets in one density cell
+    pub peak_density: u32,
+    /// widest opening in hitbox widths, capped at `Analysis::gap_cap`
+    pub widest_gap: f32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolleyReport {
+    pub index: usize,
+    pub kind: usize,
+    pub fired_at: f32,
+    /// when its last bullet left the player area
+    pub cleared_at: f32,
+    pub path: bool,
+    /// share of the player area a path through the volley starts from
+    pub safe_start: f32,
+    /// tightest opening along the best paths in hitbox widths, 0 without a path
+    pub narrowest_gap: f32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PatternReport {
+    pub name: String,
+    pub heat: f32,
+    pub bullet_speed: f32,
+    pub peak_density: u32,
+    pub undodgeable: bool,
+    pub volleys: Vec<VolleyReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StepStats>,
+    /// bullet counts per density cell for each step, rows from up to down
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub density_maps: Vec<Vec<Vec<u32>>>,
+}
+
+pub struct Analysis {
+    pub heat: f32,
+    pub fps: u32,
+    pub frames_per_step: u32,
+    pub density_cell: f32,
+    /// gaps wider than this many hitbox widths are not told apart
+    pub gap_cap: f32,
+    /// seconds simulated at most
+    pub max_seconds: f32,
+    pub keep_steps: bool,
+    pub keep_maps: bool,
+}
+
+impl Default for Analysis {
+    fn default() -> Self {
+        Analysis {
+            heat: 1.0,
+            fps: 60,
+            frames_per_step: 2,
+            density_cell: 2.0,
+            gap_cap: 4.0,
+            max_seconds: 30.0,
+            keep_steps: false,
+            keep_maps: false,
+        }
+    }
+}
+
+// cell grid over level.inner
+struct Grid {
+    left: f32,
+    up: f32,
+    cell: f32,
+    cols: usize,
+    rows: usize,
+}
+
+impl Grid {
+    fn new(level: &Level, cell: f32) -> Self {
+        let inner = level.inner;
+        Grid {
+            left: inner.left,
+            up: inner.up,
+            cell,
+            cols: ((inner.right - inner.left) / cell).ceil().max(1.0) as usize,
+            rows: ((inner.down - inner.up) / cell).ceil().max(1.0) as usize,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.cols * self.rows
+    }
+
+    fn center(&self, i: usize) -> (f32, f32) {
+        let (c, r) = (i % self.cols, i / self.cols);
+        (
+            self.left + (c as f32 + 0.5) * self.cell,
+            self.up + (r as f32 + 0.5) * self.cell,
+        )
+    }
+
+    fn index(&self, x: f32, z: f32) -> Option<usize> {
+        let c = ((x - self.left) / self.cell).floor();
+        let r = ((z - self.up) / self.cell).floor();
+        if c < 0.0 || r < 0.0 || c as usize >= self.cols || r as usize >= self.rows {
+            return None;
+        }
+        Some(r as usize * self.cols + c as usize)
+    }
+
+    // cells reachable in one step from `from`
+    fn dilate(&self, from: &[bool]) -> Vec<bool> {
+        let mut out = vec![false; from.len()];
+        for (i, _) in from.iter().enumerate().filter(|(_, &f)| f) {
+            let (c, r) = ((i % self.cols) as i64, (i / self.cols) as i64);
+            for dr in -1..=1 {
+                for dc in -1..=1 {
+                    let (nc, nr) = (c + dc, r + dr);
+                    if nc >= 0 && nr >= 0 && (nc as usize) < self.cols && (nr as usize) < self.rows
+                    {
+                        out[nr as usize * self.cols + nc as usize] = true;
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+// bullet position with the volley it belongs to
+type Snapshot = Vec<(f32, f32, usize)>;
+
+impl Analysis {
+    pub fn bullet_speed(&self, level: &Level) -> f32 {
+        level.alien_speed * BULLET_SPEED_RATIO * self.heat
+    }
+
+    /// Fires `wave` and follows its bullets until they are gone.
+    pub fn run(&self, level: &Level, name: &str, wave: &[Emit]) -> PatternReport {
+        let dt = 1.0 / self.fps.max(1) as f32;
+        let per_step = self.frames_per_step.max(1);
+        let speed = self.bullet_speed(level);
+        let reach = level.inner;
+        // bullets farther than this out of the player area can no longer hit
+        let margin = level.alien_bullet_radius + level.player_radius;
+
+        // positions every step
+        let mut snapshots: Vec<Snapshot> = Vec::new();
+        let mut fired_step = vec![None; wave.len()];
+        let mut flying: Vec<(Shot, usize)> = Vec::new();
+        let max_frames = (self.max_seconds * self.fps as f32).ceil() as u32;
+        for frame in 0..=max_frames {
+            let t = frame as f32 * dt;
+            for (i, emit) in wave.iter().enumerate() {
+                if fired_step[i].is_none() && t >= emit.at {
+                    fired_step[i] = Some((frame / per_step) as usize);
+                    let (ax, az) = emit.aim;
+                    let len = (ax * ax + az * az).sqrt().max(f32::EPSILON);
+                    let angles = level.barrage(emit.kind);
+                    let shots =
+                        volley(emit.from.0, emit.from.1, ax / len, az / len, speed, &angles);
+                    flying.extend(shots.into_iter().map(|s| (s, i)));
+                }
+            }
+            if frame % per_step == 0 {
+                snapshots.push(flying.iter().map(|(s, v)| (s.x, s.z, *v)).collect());
+            }
+            if flying.is_empty() && fired_step.iter().all(|s| s.is_some()) {
+                break;
+            }
+            for (shot, _) in flying.iter_mut() {
+                shot.advance(dt);
+            }
+            flying.retain(|(s, _)| level.outer.contains(s.x, s.z));
+        }
+        let step_time = dt * per_step as f32;
+
+        let grid = Grid::new(level, level.player_speed * step_time);
+        let slack = grid.cell * std::f32::consts::FRAC_1_SQRT_2;
+        let cap = self.gap_cap * level.player_radius;
+        let mut hash = SpatialHash::new(self.density_cell.max(cap / 2.0));
+        let mut found = Vec::new();
+        // distance from each cell center to the nearest bullet edge, per step
+        let clearance: Vec<Vec<f32>> = snapshots
+            .iter()
+            .map(|snap| {
+                hash.clear();
+                for &(x, z, _) in snap.iter() {
+                    hash.insert(Circle::new(x, z, level.alien_bullet_radius));
+                }
+                (0..grid.len())
+                    .map(|i| {
+                        let (x, z) = grid.center(i);
+                        found.clear();
+                        hash.query(&Circle::new(x, z, cap), &mut found);
+                        found
+                            .iter()
+                            .map(|&b| {
+                                let (bx, bz, _) = snap[b];
+                                ((bx - x).powi(2) + (bz - z).powi(2)).sqrt()
+                                    - level.alien_bullet_radius
+                            })
+                            .fold(cap, f32::min)
+                    })
+                    .collect()
+            })
+            .collect();
+        let free: Vec<Vec<bool>> = clearance
+            .iter()
+            .map(|c| c.iter().map(|&d| d + slack > level.player_radius).collect())
+            .collect();
+
+        let density = Grid::new(level, self.density_cell);
+        let maps: Vec<Vec<u32>> = snapshots
+            .iter()
+            .map(|snap| {
+                let mut map = vec![0; density.len()];
+                for &(x, z, _) in snap.iter() {
+                    if let Some(i) = density.index(x, z) {
+                        map[i] += 1;
+                    }
+                }
+                map
+            })
+            .collect();
+        let peak_density = maps
+            .iter()
+            .flat_map(|m| m.iter())
+            .copied()
+            .max()
+            .unwrap_or(0);
+
+        let volleys: Vec<VolleyReport> = wave
+            .iter()
+            .enumerate()
+            .filter_map(|(i, emit)| {
+                let fired = fired_step[i].filter(|&f| f < snapshots.len())?;
+                let cleared = snapshots
+                    .iter()
+                    .rposition(|snap| {
+                        snap.iter().any(|&(x, z, v)| {
+                            v == i
+                                && x > reach.left - margin
+                                && x < reach.right + margin
+                                && z > reach.up - margin
+                                && z < reach.down + margin
+                        })
+                    })
+                    .unwrap_or(fired)
+                    .max(fired);
+                // backwards from the step the volley is gone
+                let mut safe = free[cleared].clone();
+                let mut narrowest = widest(&clearance[cleared], &safe);
+                for k in (fired..cleared).rev() {
+                    let next = grid.dilate(&safe);
+                    safe = free[k].iter().zip(next).map(|(&f, n)| f && n).collect();
+                    narrowest = narrowest.min(widest(&clearance[k], &safe));
+                }
+                let starts = safe.iter().filter(|&&s| s).count();
+                Some(VolleyReport {
+                    index: i,
+                    kind: emit.kind,
+                    fired_at: fired as f32 * step_time,
+                    cleared_at: cleared as f32 * step_time,
+                    path: starts > 0,
+                    safe_start: starts as f32 / grid.len() as f32,
+                    narrowest_gap: if starts > 0 {
+                        narrowest / level.player_radius
+                    } else {
+                        0.0
+                    },
+                })
+            })
+            .collect();
+
+        let steps = if self.keep_steps {
+            snapshots
+                .iter()
+                .enumerate()
+                .map(|(k, snap)| StepStats {
+                    time: k as f32 * step_time,
+                    bullets: snap
+                        .iter()
+                        .filter(|&&(x, z, _)| reach.contains(x, z))
+                        .count(),
+                    peak_density: maps[k].iter().copied().max().unwrap_or(0),
+                    widest_gap: widest(&clearance[k], &free[k]) / level.player_radius,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let density_maps = if self.keep_maps {
+            maps.iter()
+                .map(|m| m.chunks(density.cols).map(|row| row.to_vec()).collect())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        PatternReport {
+            name: name.to_string(),
+            heat: self.heat,
+            bullet_speed: speed,
+            peak_density,
+            undodgeable: volleys.iter().any(|v| !v.path),
+            volleys,
+            steps,
+            density_maps,
+        }
+    }
+}
+
+// largest clearance among the cells in `among`, 0 when there are none
+fn widest(clearance: &[f32], among: &[bool]) -> f32 {
+    clearance
+        .iter()
+        .zip(among)
+        .filter(|(_, &a)| a)
+        .map(|(&c, _)| c)
+        .fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // one bullet straight along the aim
+    fn single_shot_level() -> Level {
+        Level {
+            barrages: vec![vec![0.0]],
+            ..Level::default()
+        }
+    }
+
+    // coarse steps keep the debug build fast
+    fn analysis() -> Analysis {
+        Analysis {
+            frames_per_step: 4,
+            ..Analysis::default()
+        }
+    }
+
+    #[test]
+    fn a_single_shot_can_be_dodged() {
+        let level = single_shot_level();
+        let wave = barrage_wave(0, (0.0, -25.0), (0.0, 1.0), 3, 0.5);
+        let report = analysis().run(&level, "single", &wave);
+        assert_eq!(report.volleys.len(), 3);
+        assert!(!report.undodgeable);
+        assert!(report
+            .volleys
+            .iter()
+            .all(|v| v.path && v.narrowest_gap > 0.0));
+    }
+
+    #[test]
+    fn a_wall_across_the_field_cannot_be_dodged() {
+        let level = single_shot_level();
+        // shots closer than a hitbox from side to side, fired together
+        let wave: Vec<Emit> = (0..=44)
+            .map(|i| Emit {
+                at: 0.0,
+                from: (-11.0 + i as f32 * 0.5, -25.0),
+                aim: (0.0, 1.0),
+                kind: 0,
+            })
+            .collect();
+        let report = analysis().run(&level, "wall", &wave);
+        assert!(report.undodgeable);
+        // the shot in the middle crosses the whole field
+        assert!(!report.volleys[22].path);
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+
+use crate::barrage::{barrage_angles, BARRAGE_KINDS};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Rect {
+    pub left: f32,
+    pub right: f32,
+    pub up: f32,
+    pub down: f32,
+}
+
+impl Rect {
+    pub fn contains(&self, x: f32, z: f32) -> bool {
+        x >= self.left && x <= self.right && z >= self.up && z <= self.down
+    }
+
+    pub fn clamp(&self, x: f32, z: f32) -> (f32, f32) {
+        (
+            x.max(self.left).min(self.right),
+            z.max(self.up).min(self.down),
+        )
+    }
+}
+
+/// Tuning of a simulated stage. Every field has the value of stage.tscn when left out of the file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Level {
+    /// players move inside, aliens turn at the sides
+    pub inner: Rect,
+    /// aliens and bullets outside are despawned
+    pub outer: Rect,
+    pub spawn_top_z: f32,
+    pub spawn_side_x: f32,
+
+    pub player_life: i32,
+    pub player_speed: f32,
+    pub focus_speed_weight: f32,
+    pub shoot_speed_weight: f32,
+    pub bullet_speed: f32,
+    pub respawn_time: f32,
+    pub invulnerable_time: f32,
+
+    pub alien_speed: f32,
+    pub alien_fire_min: f32,
+    pub alien_fire_max: f32,
+    pub spawn_min: f32,
+    pub spawn_max: f32,
+
+    pub heat_every: u32,
+    pub heat_rate: f32,
+    pub extend_every: u32,
+
+    pub bullet_pool: usize,
+    pub alien_pool: usize,
+    pub alien_bullet_pool: usize,
+
+    pub player_radius: f32,
+    pub alien_radius: f32,
+    pub bullet_radius: f32,
+    pub alien_bullet_radius: f32,
+
+    /// degrees relative to the aimed direction for each barrage kind, the game barrages when empty
+    pub barrages: Vec<Vec<f32>>,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level {
+            inner: Rect {
+                left: -10.0,
+                right: 10.0,
+                up: -20.0,
+                down: 1.0,
+            },
+            outer: Rect {
+                left: -25.0,
+                right: 25.0,
+                up: -30.0,
+                down: 6.0,
+            },
+            spawn_top_z: -27.0,
+            spawn_side_x: 22.0,
+
+            player_life: 3,
+            player_speed: 10.0,
+            focus_speed_weight: 0.5,
+            shoot_speed_weight: 0.5,
+            bullet_speed: 15.0,
+            respawn_time: 1.0,
+            invulnerable_time: 4.0,
+
+            alien_speed: 5.0,
+            alien_fire_min: 2.0,
+            alien_fire_max: 4.0,
+            spawn_min: 0.5,
+            spawn_max: 3.0,
+
+            heat_every: 10,
+            heat_rate: 1.1,
+            extend_every: 20,
+
+            bullet_pool: 100,
+            alien_pool: 100,
+            alien_bullet_pool: 200,
+
+            player_radius: 0.9,
+            alien_radius: 0.8,
+            bullet_radius: 0.1,
+            alien_bullet_radius: 0.25,
+
+            barrages: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(String, io::Error),
+    Parse(String, serde_json::Error),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(path, e) => write!(f, "failed to read {}: {}", path, e),
+            LoadError::Parse(path, e) => write!(f, "failed to parse {}: {}", path, e),
+        }
+    }
+}
+
+pub(crate) fn load_json<T: serde::de::DeserializeOwned>(path: &str) -> Result<T, LoadError> {
+    let text = fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_string(), e))?;
+    serde_json::from_str(&text).map_err(|e| LoadError::Parse(path.to_string(), e))
+}
+
+impl Level {
+    /// Loads a level JSON file. A file with only `barrages` is a barrage file.
+    pub fn load(path: &str) -> Result<Self, LoadError> {
+        load_json(path)
+    }
+
+    pub fn barrage_kinds(&self) -> usize {
+        if self.barrages.is_empty() {
+            BARRAGE_KINDS
+        } else {
+            self.barrages.len()
+        }
+    }
+
+    /// Angles in radians of the bullets of barrage `kind`.
+    pub fn barrage(&self, kind: usize) -> Vec<f32> {
+        if self.barrages.is_empty() {
+            barrage_angles(kind)
+        } else {
+            self.barrages[kind % self.barrages.len()]
+                .iter()
+                .map(|deg| deg.to_radians())
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `key = value` of node `node` in the text of a .tscn file
+    fn scene_value(scene: &str, node: &str, key: &str) -> f32 {
+        let header = format!("[node name=\"{}\"", node);
+        let prefix = format!("{} = ", key);
+        scene
+            .lines()
+            .skip_while(|line| !line.starts_with(&header))
+            .skip(1)
+            .take_while(|line| !line.starts_with('['))
+            .find_map(|line| line.strip_prefix(&prefix))
+            .unwrap_or_else(|| panic!("no {} in node {}", key, node))
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn defaults_match_the_scenes() {
+        let level = Level::default();
+        let alien = include_str!("../../../shooting_gdt/alien.tscn");
+        assert_eq!(
+            level.alien_fire_min,
+            scene_value(alien, "alien", "default_min_fire_interval")
+        );
+        assert_eq!(
+            level.alien_fire_max,
+            scene_value(alien, "alien", "default_max_fire_interval")
+        );
+        let stage = include_str!("../../../shooting_gdt/stage.tscn");
+        assert_eq!(
+            level.player_speed,
+            scene_value(stage, "PlayerRoot", "speed")
+        );
+        let bullet = include_str!("../../../shooting_gdt/bullet.tscn");
+        assert_eq!(level.bullet_speed, scene_value(bullet, "bullet", "speed"));
+    }
+}
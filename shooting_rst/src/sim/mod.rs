@@ -0,0 +1,18 @@
+//! The game rules without Godot, for balance checks and replay verification.
+//!
+//! Mirrors Stage, Player and Alien on the xz plane for one player.
+//! Sounds, effects, option pods, co-op and continues are left out.
+
+mod dodge;
+mod level;
+mod policy;
+mod rng;
+mod verify;
+mod world;
+
+pub use dodge::{barrage_wave, Analysis, Emit, PatternReport, StepStats, VolleyReport};
+pub use level::{Level, LoadError, Rect};
+pub use policy::{AutopilotPolicy, Input, InputPolicy, RandomPolicy, ReplayPolicy, ScriptedPolicy};
+pub use rng::Rng;
+pub use verify::{verify, Claim, Verdict};
+pub use world::{HeatPoint, HighWater, Summary, World};
@@ -0,0 +1,181 @@
+use serde::Deserialize;
+
+use super::level::{load_json, LoadError};
+use super::rng::Rng;
+use super::world::World;
+use crate::autopilot::{Autopilot, Limits, Ship, Threat};
+use crate::replay::{Replay, ReplayInput};
+
+/// One tick of controller state, the stick is clamped to length 1.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Input {
+    pub x: f32,
+    pub z: f32,
+    pub shoot: bool,
+    pub focus: bool,
+}
+
+impl From<ReplayInput> for Input {
+    fn from(input: ReplayInput) -> Self {
+        Input {
+            x: input.x,
+            z: input.z,
+            shoot: input.shoot,
+            focus: input.focus,
+        }
+    }
+}
+
+impl From<Input> for ReplayInput {
+    /// The stick is clamped to -1..1 on each axis, all a replay holds.
+    fn from(input: Input) -> Self {
+        ReplayInput {
+            x: input.x.clamp(-1.0, 1.0),
+            z: input.z.clamp(-1.0, 1.0),
+            shoot: input.shoot,
+            focus: input.focus,
+        }
+    }
+}
+
+pub trait InputPolicy {
+    fn input(&mut self, world: &World) -> Input;
+}
+
+/// Holds a random stick direction for a while, always shooting.
+pub struct RandomPolicy {
+    rng: Rng,
+    current: Input,
+    hold_until: f32,
+}
+
+impl RandomPolicy {
+    pub fn new(seed: u64) -> Self {
+        RandomPolicy {
+            rng: Rng::new(seed.wrapping_add(1)),
+            current: Input::default(),
+            hold_until: 0.0,
+        }
+    }
+}
+
+impl InputPolicy for RandomPolicy {
+    fn input(&mut self, world: &World) -> Input {
+        if world.time() >= self.hold_until {
+            self.current = Input {
+                x: self.rng.range_i64(-1, 1) as f32,
+                z: self.rng.range_i64(-1, 1) as f32,
+                shoot: true,
+                focus: self.rng.range_i64(0, 3) == 0,
+            };
+            self.hold_until = world.time() + self.rng.range_f32(0.2, 1.0);
+        }
+        self.current
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ScriptStep {
+    /// seconds this input is held until
+    until: f32,
+    #[serde(flatten)]
+    input: Input,
+}
+
+/// Plays back a JSON list of `{"until": 2.0, "x": -1, "shoot": true}` steps, then idles.
+pub struct ScriptedPolicy {
+    steps: Vec<ScriptStep>,
+}
+
+impl ScriptedPolicy {
+    pub fn load(path: &str) -> Result<Self, LoadError> {
+        Ok(ScriptedPolicy {
+            steps: load_json(path)?,
+        })
+    }
+}
+
+impl InputPolicy for ScriptedPolicy {
+    fn input(&mut self, world: &World) -> Input {
+        self.steps
+            .iter()
+            .find(|s| world.time() < s.until)
+            .map(|s| s.input)
+            .unwrap_or_default()
+    }
+}
+
+/// Flies with `autopilot::Autopilot`, for trying out barrages without a human.
+#[derive(Default)]
+pub struct AutopilotPolicy {
+    pilot: Autopilot,
+}
+
+impl AutopilotPolicy {
+    pub fn new(pilot: Autopilot) -> Self {
+        AutopilotPolicy { pilot }
+    }
+}
+
+impl InputPolicy for AutopilotPolicy {
+    fn input(&mut self, world: &World) -> Input {
+        let (x, z) = match world.player_pos() {
+            Some(pos) => pos,
+            None => return Input::default(),
+        };
+        let level = world.level();
+        let ship = Ship {
+            x,
+            z,
+            r: level.player_radius,
+            speed: level.player_speed,
+            focus_speed: level.player_speed * level.focus_speed_weight,
+            shoot_weight: level.shoot_speed_weight,
+        };
+        let limits = Limits {
+            left: level.inner.left,
+            right: level.inner.right,
+            up: level.inner.up,
+            down: level.inner.down,
+        };
+        let threats: Vec<Threat> = world
+            .alien_bullets()
+            .map(|(x, z, vx, vz)| Threat::new(x, z, vx, vz, level.alien_bullet_radius))
+            .chain(
+                world
+                    .alien_motions()
+                    .map(|(x, z, vx, vz)| Threat::new(x, z, vx, vz, level.alien_radius)),
+            )
+            .collect();
+        let targets: Vec<(f32, f32)> = world.aliens().collect();
+
+        let d = self.pilot.decide(&ship, &limits, &threats, &targets);
+        Input {
+            x: d.x,
+            z: d.z,
+            shoot: d.shoot,
+            focus: d.focus,
+        }
+    }
+}
+
+/// Plays back a recorded replay one frame per tick, then idles.
+pub struct ReplayPolicy {
+    replay: Replay,
+    frame: u32,
+}
+
+impl ReplayPolicy {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPolicy { replay, frame: 0 }
+    }
+}
+
+impl InputPolicy for ReplayPolicy {
+    fn input(&mut self, _world: &World) -> Input {
+        let input = self.replay.input(self.frame).map(Input::from);
+        self.frame += 1;
+        input.unwrap_or_default()
+    }
+}
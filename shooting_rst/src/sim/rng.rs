@@ -0,0 +1,40 @@
+/// xorshift64*, so a seed gives the same run on every platform.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        Rng(if state == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            state
+        })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// In [0, 1).
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// In [min, max], like `RandomNumberGenerator::randi_range`.
+    pub fn range_i64(&mut self, min: i64, max: i64) -> i64 {
+        if max <= min {
+            return min;
+        }
+        min + (self.next_u64() % (max - min + 1) as u64) as i64
+    }
+}
@@ -0,0 +1,216 @@
+//! Re-runs a replay on the sim core and checks what a leaderboard entry claims it came to.
+//!
+//! Only replays recorded on the core, by `sim --record`, are checked. The Godot game does not
+//! run on the rules of `World`, so its replays are not checked at all: their verdict is
+//! unverified, and a board keeps such an entry off its standings.
+
+use serde::Serialize;
+
+use super::level::Level;
+use super::policy::{InputPolicy, ReplayPolicy};
+use super::world::World;
+use crate::replay::{Replay, Rules};
+
+// frames the claimed duration may be off by, the run and its recording may start a frame apart
+const SLACK_FRAMES: u32 = 2;
+
+/// Score, kills and duration of a run.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Claim {
+    pub score: i32,
+    pub kills: i32,
+    pub seconds: f32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Verdict {
+    pub accepted: bool,
+    /// false when the replay was recorded under rules the core cannot re-run,
+    /// nothing was checked then and the entry is not to be ranked
+    pub verified: bool,
+    /// why the entry is rejected, empty when accepted
+    pub mismatches: Vec<String>,
+    pub claimed: Claim,
+    /// what the replay came to, the duration up to the game over, `None` when not re-run
+    pub replayed: Option<Claim>,
+    /// frames stepped until the game over
+    pub frames: u32,
+    pub game_over: bool,
+}
+
+/// Steps `replay` through a world of `level` until the game is over or the inputs run out,
+/// and compares the outcome with `claim`. The Stage scores one point a kill and times a run
+/// in physics frames, so the duration is counted in frames too.
+pub fn verify(level: Level, replay: &Replay, claim: Claim) -> Verdict {
+    if replay.rules != Rules::Sim {
+        return Verdict {
+            accepted: false,
+            verified: false,
+            mismatches: Vec::new(),
+            claimed: claim,
+            replayed: None,
+            frames: 0,
+            game_over: false,
+        };
+    }
+    let fps = replay.fps.max(1);
+    let mut world = World::new(level, replay.seed);
+    let mut policy = ReplayPolicy::new(replay.clone());
+    let mut frames = 0;
+    while frames < replay.len() && !world.is_game_over() {
+        let input = policy.input(&world);
+        world.step(1.0 / fps as f32, input);
+        frames += 1;
+    }
+
+    let kills = world.kills() as i32;
+    let replayed = Claim {
+        score: kills,
+        kills,
+        seconds: frames as f32 / fps as f32,
+    };
+    let slack = SLACK_FRAMES as f32 / fps as f32;
+    let mut mismatches = Vec::new();
+    if !world.is_game_over() {
+        mismatches.push("the replay ends before the game is over".to_string());
+    } else if replay.len() - frames > SLACK_FRAMES {
+        mismatches.push(format!(
+            "the replay goes on {} frames past the game over",
+            replay.len() - frames
+        ));
+    }
+    if claim.score != replayed.score {
+        mismatches.push(format!(
+            "score: claimed {}, replayed {}",
+            claim.score, replayed.score
+        ));
+    }
+    if claim.kills != replayed.kills {
+        mismatches.push(format!(
+            "kills: claimed {}, replayed {}",
+            claim.kills, replayed.kills
+        ));
+    }
+    if (claim.seconds - replayed.seconds).abs() > slack {
+        mismatches.push(format!(
+            "seconds: claimed {:.2}, replayed {:.2}",
+            claim.seconds, replayed.seconds
+        ));
+    }
+    Verdict {
+        accepted: mismatches.is_empty(),
+        verified: true,
+        mismatches,
+        claimed: claim,
+        replayed: Some(replayed),
+        frames,
+        game_over: world.is_game_over(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::ReplayInput;
+    use crate::sim::RandomPolicy;
+
+    // a run of the sim core played to the game over, with what it came to
+    fn recorded(seed: u64) -> (Replay, Claim) {
+        let mut world = World::new(Level::default(), seed);
+        let mut policy = RandomPolicy::new(seed);
+        let mut replay = Replay::new(seed, 60);
+        while !world.is_game_over() {
+            let input = ReplayInput::from(policy.input(&world));
+            replay.push(input).unwrap();
+            world.step(1.0 / 60.0, input.into());
+        }
+        let kills = world.kills() as i32;
+        let claim = Claim {
+            score: kills,
+            kills,
+            seconds: replay.seconds(),
+        };
+        (replay, claim)
+    }
+
+    // the first `frames` inputs of `replay`
+    fn cut(replay: &Replay, frames: usize) -> Replay {
+        let mut cut = Replay::new(replay.seed, replay.fps);
+        for input in replay.inputs().take(frames) {
+            cut.push(input).unwrap();
+        }
+        cut
+    }
+
+    #[test]
+    fn accepts_a_run_of_the_core() {
+        let (replay, claim) = recorded(3);
+        let verdict = verify(Level::default(), &replay, claim);
+        assert!(verdict.accepted, "{:?}", verdict.mismatches);
+        assert!(verdict.verified && verdict.game_over);
+        assert_eq!(verdict.replayed, Some(claim));
+    }
+
+    #[test]
+    fn rejects_a_wrong_claim() {
+        let (replay, claim) = recorded(3);
+        let wrong = Claim {
+            score: claim.score + 1,
+            kills: claim.kills + 1,
+            seconds: claim.seconds + 1.0,
+        };
+        let verdict = verify(Level::default(), &replay, wrong);
+        assert!(verdict.verified && !verdict.accepted);
+        assert_eq!(verdict.mismatches.len(), 3);
+
+        // a frame off is the recording starting a frame late
+        let close = Claim {
+            seconds: claim.seconds + 1.0 / 60.0,
+            ..claim
+        };
+        assert!(verify(Level::default(), &replay, close).accepted);
+    }
+
+    #[test]
+    fn rejects_a_replay_cut_or_padded() {
+        let (replay, claim) = recorded(3);
+        let frames = replay.len() as usize;
+        let short = cut(&replay, frames - 60);
+        let verdict = verify(Level::default(), &short, claim);
+        assert!(!verdict.accepted && !verdict.game_over);
+
+        let mut long = cut(&replay, frames);
+        for _ in 0..=SLACK_FRAMES {
+            long.push(ReplayInput::default()).unwrap();
+        }
+        assert!(!verify(Level::default(), &long, claim).accepted);
+    }
+
+    #[test]
+    fn holds_replays_recorded_by_the_game() {
+        // what the Stage records: the first player's stick, shoot and focus every physics frame
+        let mut replay = Replay::new(7, 60);
+        replay.rules = Rules::Game;
+        for frame in 0..600 {
+            let x = if frame % 120 < 60 { -1.0 } else { 0.5 };
+            replay
+                .push(ReplayInput {
+                    x,
+                    z: 0.0,
+                    shoot: true,
+                    focus: frame > 300,
+                })
+                .unwrap();
+        }
+        let replay = Replay::parse(&replay.to_string()).unwrap();
+        let claim = Claim {
+            score: 4,
+            kills: 4,
+            seconds: replay.seconds(),
+        };
+        let verdict = verify(Level::default(), &replay, claim);
+        assert!(!verdict.verified && !verdict.accepted);
+        assert!(verdict.mismatches.is_empty());
+        assert_eq!(verdict.replayed, None);
+    }
+}
@@ -0,0 +1,599 @@
+use serde::Serialize;
+use std::f32::consts::PI;
+
+use super::level::Level;
+use super::policy::{Input, InputPolicy};
+use super::rng::Rng;
+use crate::barrage::{rotate_xz, volley, Shot, BULLET_SPEED_RATIO};
+use crate::collision::{self, Circle, SpatialHash};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pattern {
+    Invasion,
+    Dir,
+}
+
+struct Alien {
+    x: f32,
+    z: f32,
+    dx: f32,
+    dz: f32,
+    speed: f32,
+    pattern: Pattern,
+    fire_span: f32,
+    fire_left: f32,
+    dir_span: f32,
+    dir_left: f32,
+}
+
+struct Player {
+    x: f32,
+    z: f32,
+    alive: bool,
+    life: i32,
+    respawn_left: f32,
+    invulnerable_left: f32,
+    barrel: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct HeatPoint {
+    pub time: f32,
+    pub heat: f32,
+}
+
+/// Most objects out of each pool at once.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct HighWater {
+    pub aliens: usize,
+    pub bullets: usize,
+    pub alien_bullets: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Summary {
+    pub seed: u64,
+    pub seconds: f32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub game_over_at: Option<f32>,
+    pub bullets_fired: u32,
+    pub alien_bullets_fired: u32,
+    pub pool_exhausted: u32,
+    pub high_water: HighWater,
+    pub heat_curve: Vec<HeatPoint>,
+}
+
+pub struct World {
+    level: Level,
+    seed: u64,
+    rng: Rng,
+    grid: SpatialHash,
+    time: f32,
+    heat: f32,
+    spawn_left: f32,
+    player: Player,
+    aliens: Vec<Alien>,
+    bullets: Vec<Shot>,
+    alien_bullets: Vec<Shot>,
+
+    kills: u32,
+    deaths: u32,
+    game_over_at: Option<f32>,
+    bullets_fired: u32,
+    alien_bullets_fired: u32,
+    pool_exhausted: u32,
+    high_water: HighWater,
+    heat_curve: Vec<HeatPoint>,
+}
+
+impl World {
+    pub fn new(level: Level, seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let spawn_left = rng.range_f32(0.5, 1.0);
+        let player = Player {
+            x: 0.0,
+            z: 0.0,
+            alive: true,
+            life: level.player_life,
+            respawn_left: 0.0,
+            invulnerable_left: level.invulnerable_time,
+            barrel: false,
+        };
+        World {
+            level,
+            seed,
+            rng,
+            grid: SpatialHash::new(2.0),
+            time: 0.0,
+            heat: 1.0,
+            spawn_left,
+            player,
+            aliens: Vec::new(),
+            bullets: Vec::new(),
+            alien_bullets: Vec::new(),
+
+            kills: 0,
+            deaths: 0,
+            game_over_at: None,
+            bullets_fired: 0,
+            alien_bullets_fired: 0,
+            pool_exhausted: 0,
+            high_water: HighWater::default(),
+            heat_curve: vec![HeatPoint {
+                time: 0.0,
+                heat: 1.0,
+            }],
+        }
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn heat(&self) -> f32 {
+        self.heat
+    }
+
+    pub fn kills(&self) -> u32 {
+        self.kills
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over_at.is_some()
+    }
+
+    /// `None` while the player is down.
+    pub fn player_pos(&self) -> Option<(f32, f32)> {
+        if self.player.alive {
+            Some((self.player.x, self.player.z))
+        } else {
+            None
+        }
+    }
+
+    pub fn aliens(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.aliens.iter().map(|a| (a.x, a.z))
+    }
+
+    /// Positions and velocities of the aliens.
+    pub fn alien_motions(&self) -> impl Iterator<Item = (f32, f32, f32, f32)> + '_ {
+        self.aliens
+            .iter()
+            .map(|a| (a.x, a.z, a.dx * a.speed, a.dz * a.speed))
+    }
+
+    /// Positions and velocities of the alien bullets.
+    pub fn alien_bullets(&self) -> impl Iterator<Item = (f32, f32, f32, f32)> + '_ {
+        self.alien_bullets.iter().map(|b| (b.x, b.z, b.vx, b.vz))
+    }
+
+    /// Runs `seconds` at `fps` ticks per second, or until the game is over.
+    pub fn run(&mut self, policy: &mut dyn InputPolicy, seconds: f32, fps: u32) {
+        let dt = 1.0 / fps.max(1) as f32;
+        let ticks = (seconds * fps as f32).ceil() as u64;
+        for _ in 0..ticks {
+            if self.is_game_over() {
+                break;
+            }
+            let input = policy.input(self);
+            self.step(dt, input);
+        }
+    }
+
+    pub fn step(&mut self, dt: f32, input: Input) {
+        self.time += dt;
+        self.update_player(dt, input);
+        self.update_spawn(dt);
+        self.update_aliens(dt);
+
+        let outer = self.level.outer;
+        for b in self.bullets.iter_mut().chain(self.alien_bullets.iter_mut()) {
+            b.advance(dt);
+        }
+        self.bullets.retain(|b| outer.contains(b.x, b.z));
+        self.alien_bullets.retain(|b| outer.contains(b.x, b.z));
+
+        self.collide();
+
+        self.high_water.aliens = self.high_water.aliens.max(self.aliens.len());
+        self.high_water.bullets = self.high_water.bullets.max(self.bullets.len());
+        self.high_water.alien_bullets = self.high_water.alien_bullets.max(self.alien_bullets.len());
+    }
+
+    fn update_player(&mut self, dt: f32, input: Input) {
+        let level = &self.level;
+        let p = &mut self.player;
+        if !p.alive {
+            if self.game_over_at.is_some() {
+                return;
+            }
+            p.respawn_left -= dt;
+            if p.respawn_left <= 0.0 {
+                p.alive = true;
+                p.x = 0.0;
+                p.z = 0.0;
+                p.invulnerable_left = level.invulnerable_time;
+                // clear_bullets_on_respawn
+                self.alien_bullets.clear();
+            }
+            return;
+        }
+
+        p.invulnerable_left -= dt;
+        let len = (input.x * input.x + input.z * input.z).sqrt();
+        let (sx, sz) = if len > 1.0 {
+            (input.x / len, input.z / len)
+        } else {
+            (input.x, input.z)
+        };
+        let weight = if input.shoot {
+            level.shoot_speed_weight
+        } else {
+            1.0
+        };
+        let weight = if input.focus {
+            weight * level.focus_speed_weight
+        } else {
+            weight
+        };
+        let (x, z) = level.inner.clamp(
+            p.x + sx * level.player_speed * weight * dt,
+            p.z + sz * level.player_speed * weight * dt,
+        );
+        p.x = x;
+        p.z = z;
+
+        if input.shoot {
+            if self.bullets.len() >= level.bullet_pool {
+                self.pool_exhausted += 1;
+                return;
+            }
+            p.barrel = !p.barrel;
+            let barrel_x = if p.barrel { -0.45 } else { 0.45 };
+            self.bullets.push(Shot {
+                x: p.x + barrel_x,
+                z: p.z - 0.8,
+                vx: 0.0,
+                vz: -level.bullet_speed,
+            });
+            self.bullets_fired += 1;
+        }
+    }
+
+    fn update_spawn(&mut self, dt: f32) {
+        self.spawn_left -= dt;
+        if self.spawn_left > 0.0 {
+            return;
+        }
+        let level = &self.level;
+        let rng = &mut self.rng;
+        self.spawn_left = rng.range_f32(level.spawn_min, level.spawn_max) / self.heat;
+        if self.aliens.len() >= level.alien_pool {
+            self.pool_exhausted += 1;
+            return;
+        }
+
+        let (x, z, dx, dz, pattern) = if rng.range_i64(0, 1) == 0 {
+            let x = rng.range_f32(level.inner.left, level.inner.right);
+            (x, level.spawn_top_z, 0.0, 1.0, Pattern::Invasion)
+        } else {
+            let mid = (level.inner.up + level.inner.down) / 2.0;
+            let z = rng.range_f32(level.inner.up, mid);
+            let t = if rng.range_i64(0, 1) == 0 { -1.0 } else { 1.0 };
+            (t * level.spawn_side_x, z, -t, 0.0, Pattern::Dir)
+        };
+        let dir_span = rng.range_f32(0.0, 3.0) / self.heat;
+        let fire_span = rng.range_f32(level.alien_fire_min, level.alien_fire_max) / self.heat;
+        self.aliens.push(Alien {
+            x,
+            z,
+            dx,
+            dz,
+            speed: level.alien_speed * self.heat,
+            pattern,
+            fire_span,
+            fire_left: fire_span,
+            dir_span,
+            dir_left: dir_span,
+        });
+    }
+
+    fn update_aliens(&mut self, dt: f32) {
+        let target = self.player_pos();
+        let mut fired = Vec::new();
+        for a in self.aliens.iter_mut() {
+            a.x += a.dx * a.speed * dt;
+            a.z += a.dz * a.speed * dt;
+
+            if a.pattern == Pattern::Dir && a.dir_span > 0.0 {
+                a.dir_left -= dt;
+                if a.dir_left <= 0.0 {
+                    a.dir_left += a.dir_span;
+                    let (dx, dz) = rotate_xz(a.dx, a.dz, self.rng.range_f32(-PI / 4.0, PI / 4.0));
+                    a.dx = dx;
+                    a.dz = dz;
+                }
+            }
+
+            a.fire_left -= dt;
+            if a.fire_left <= 0.0 {
+                a.fire_left += a.fire_span.max(dt);
+                let aimed = if self.rng.range_i64(0, 1) == 0 {
+                    None
+                } else {
+                    target
+                };
+                let (dx, dz) = match aimed {
+                    Some((px, pz)) => {
+                        let (vx, vz) = (px - a.x, pz - a.z);
+                        let len = (vx * vx + vz * vz).sqrt().max(f32::EPSILON);
+                        (vx / len, vz / len)
+                    }
+                    None => (a.dx, a.dz),
+                };
+                let kind = self.rng.range_i64(0, self.level.barrage_kinds() as i64 - 1);
+                fired.push((a.x, a.z, dx, dz, a.speed * BULLET_SPEED_RATIO, kind as usize));
+            }
+        }
+        let outer = self.level.outer;
+        self.aliens.retain(|a| outer.contains(a.x, a.z));
+
+        for (x, z, dx, dz, speed, kind) in fired {
+            for shot in volley(x, z, dx, dz, speed, &self.level.barrage(kind)) {
+                if self.alien_bullets.len() >= self.level.alien_bullet_pool {
+                    // the rest of the barrage is dropped
+                    self.pool_exhausted += 1;
+                    break;
+                }
+                self.alien_bullets.push(shot);
+                self.alien_bullets_fired += 1;
+            }
+        }
+    }
+
+    fn collide(&mut self) {
+        let level = &self.level;
+        let bullets: Vec<_> = self
+            .bullets
+            .iter()
+            .map(|b| Circle::new(b.x, b.z, level.bullet_radius))
+            .collect();
+        let aliens: Vec<_> = self
+            .aliens
+            .iter()
+            .map(|a| Circle::new(a.x, a.z, level.alien_radius))
+            .collect();
+        let hits = collision::collide(&mut self.grid, &bullets, &aliens);
+        let mut dead_bullets: Vec<_> = hits.iter().map(|&(b, _)| b).collect();
+        let mut dead_aliens: Vec<_> = hits.iter().map(|&(_, a)| a).collect();
+        remove_indexes(&mut self.bullets, &mut dead_bullets);
+        let killed = remove_indexes(&mut self.aliens, &mut dead_aliens);
+        for _ in 0..killed {
+            self.alien_beated();
+        }
+
+        if !self.player.alive || self.player.invulnerable_left > 0.0 {
+            return;
+        }
+        let level = &self.level;
+        let player = [Circle::new(
+            self.player.x,
+            self.player.z,
+            level.player_radius,
+        )];
+        let alien_bullets: Vec<_> = self
+            .alien_bullets
+            .iter()
+            .map(|b| Circle::new(b.x, b.z, level.alien_bullet_radius))
+            .collect();
+        let hit_by_bullet = !collision::collide(&mut self.grid, &alien_bullets, &player).is_empty();
+        let aliens: Vec<_> = self
+            .aliens
+            .iter()
+            .map(|a| Circle::new(a.x, a.z, level.alien_radius))
+            .collect();
+        let hit_by_alien = !collision::collide(&mut self.grid, &aliens, &player).is_empty();
+        if hit_by_bullet || hit_by_alien {
+            self.player_crashed();
+        }
+    }
+
+    fn alien_beated(&mut self) {
+        self.kills += 1;
+        if self.level.heat_every > 0 && self.kills.is_multiple_of(self.level.heat_every) {
+            self.heat *= self.level.heat_rate;
+            self.heat_curve.push(HeatPoint {
+                time: self.time,
+                heat: self.heat,
+            });
+        }
+        if self.level.extend_every > 0
+            && self.kills.is_multiple_of(self.level.extend_every)
+            && self.player.life > 0
+        {
+            self.player.life += 1;
+        }
+    }
+
+    fn player_crashed(&mut self) {
+        self.deaths += 1;
+        self.player.alive = false;
+        self.player.life -= 1;
+        if self.player.life > 0 {
+            self.player.respawn_left = self.level.respawn_time;
+        } else {
+            self.game_over_at = Some(self.time);
+        }
+    }
+
+    pub fn summary(&self) -> Summary {
+        Summary {
+            seed: self.seed,
+            seconds: self.time,
+            kills: self.kills,
+            deaths: self.deaths,
+            game_over_at: self.game_over_at,
+            bullets_fired: self.bullets_fired,
+            alien_bullets_fired: self.alien_bullets_fired,
+            pool_exhausted: self.pool_exhausted,
+            high_water: self.high_water,
+            heat_curve: self.heat_curve.clone(),
+        }
+    }
+}
+
+// returns how many were removed
+fn remove_indexes<T>(items: &mut Vec<T>, indexes: &mut Vec<usize>) -> usize {
+    indexes.sort_unstable();
+    indexes.dedup();
+    for &i in indexes.iter().rev() {
+        items.swap_remove(i);
+    }
+    indexes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{RandomPolicy, Rect};
+
+    const DT: f32 = 1.0 / 60.0;
+
+    // aliens that hold their fire and stay in the field
+    fn quiet_level() -> Level {
+        Level {
+            alien_fire_min: 1000.0,
+            alien_fire_max: 1000.0,
+            outer: Rect {
+                left: -1000.0,
+                right: 1000.0,
+                up: -1000.0,
+                down: 1000.0,
+            },
+            invulnerable_time: 1000.0,
+            ..Level::default()
+        }
+    }
+
+    // a world with no spawns and one still alien at x, z
+    fn world_with_alien(level: Level, x: f32, z: f32) -> World {
+        let mut world = World::new(level, 1);
+        world.spawn_left = f32::INFINITY;
+        world.aliens.push(Alien {
+            x,
+            z,
+            dx: 0.0,
+            dz: 1.0,
+            speed: 0.0,
+            pattern: Pattern::Invasion,
+            fire_span: 1000.0,
+            fire_left: 1000.0,
+            dir_span: 0.0,
+            dir_left: 0.0,
+        });
+        world
+    }
+
+    #[test]
+    fn aliens_spawn_at_the_level_cadence() {
+        let level = quiet_level();
+        let (min, max) = (level.spawn_min, level.spawn_max);
+        let mut world = World::new(level, 7);
+        let mut spawned_at = Vec::new();
+        while world.time() < 60.0 {
+            let before = world.aliens().count();
+            world.step(DT, Input::default());
+            if world.aliens().count() > before {
+                spawned_at.push(world.time());
+            }
+        }
+        // the first one comes within a second, as in the Stage
+        assert!((0.5..=1.0 + DT).contains(&spawned_at[0]));
+        assert!(spawned_at.len() > 60 / 3);
+        for pair in spawned_at.windows(2) {
+            let span = pair[1] - pair[0];
+            assert!(
+                span >= min - DT && span <= max + DT,
+                "spawned {} apart",
+                span
+            );
+        }
+    }
+
+    #[test]
+    fn shot_aliens_count_as_kills() {
+        let mut world = world_with_alien(quiet_level(), 0.0, -4.0);
+        let shoot = Input {
+            shoot: true,
+            ..Input::default()
+        };
+        for _ in 0..60 {
+            world.step(DT, shoot);
+        }
+        assert_eq!(world.kills(), 1);
+        assert_eq!(world.aliens().count(), 0);
+        assert_eq!(world.summary().kills, 1);
+        assert!(!world.is_game_over());
+    }
+
+    #[test]
+    fn a_hit_on_the_last_life_is_game_over() {
+        let mut level = quiet_level();
+        level.player_life = 1;
+        level.invulnerable_time = 0.0;
+        let mut world = world_with_alien(level, 0.0, 0.0);
+        world.step(DT, Input::default());
+        assert!(world.is_game_over());
+        assert_eq!(world.player_pos(), None);
+        assert_eq!(world.summary().game_over_at, Some(world.time()));
+
+        // nothing respawns after the game over
+        for _ in 0..120 {
+            world.step(DT, Input::default());
+        }
+        assert_eq!(world.player_pos(), None);
+        assert_eq!(world.summary().deaths, 1);
+    }
+
+    #[test]
+    fn a_hit_with_lives_left_respawns() {
+        let mut level = quiet_level();
+        level.invulnerable_time = 0.0;
+        let respawn = level.respawn_time;
+        let mut world = world_with_alien(level, 0.0, 0.0);
+        world.step(DT, Input::default());
+        assert!(!world.is_game_over());
+        assert_eq!(world.player_pos(), None);
+        world.aliens.clear();
+        for _ in 0..((respawn / DT) as usize + 2) {
+            world.step(DT, Input::default());
+        }
+        assert_eq!(world.player_pos(), Some((0.0, 0.0)));
+    }
+
+    // the summary and where everything is after `seconds` of play
+    fn play(seed: u64, seconds: f32) -> String {
+        let mut world = World::new(Level::default(), seed);
+        world.run(&mut RandomPolicy::new(seed), seconds, 60);
+        let aliens: Vec<_> = world.alien_motions().collect();
+        let bullets: Vec<_> = world.alien_bullets().collect();
+        format!(
+            "{} {:?} {:?} {:?}",
+            serde_json::to_string(&world.summary()).unwrap(),
+            world.player_pos(),
+            aliens,
+            bullets
+        )
+    }
+
+    #[test]
+    fn a_seed_plays_the_same_run() {
+        assert_eq!(play(5, 20.0), play(5, 20.0));
+        assert_ne!(play(5, 20.0), play(6, 20.0));
+    }
+}
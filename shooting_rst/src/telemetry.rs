@@ -0,0 +1,117 @@
+//! Opt-in per-run summaries, queued on disk and POSTed in batches to an HTTP endpoint.
+//!
+//! The game sends the batches through Godot's `HTTPRequest`, see `godot::telemetry`.
+//! `cargo run --no-default-features --features sim --bin collector` receives them in development.
+
+use crate::events::DeathCause;
+use crate::json;
+
+/// Bumped when fields change meaning, the collector keeps whatever it gets.
+pub const SCHEMA: u32 = 2;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Death {
+    /// seconds into the run
+    pub at: f32,
+    pub player: usize,
+    pub cause: DeathCause,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunSummary {
+    pub seed: u64,
+    pub seconds: f32,
+    pub scores: Vec<i32>,
+    pub kills: i32,
+    pub max_heat: f32,
+    pub continues: i32,
+    pub deaths: Vec<Death>,
+    /// "game_over", "quit" or "restart"
+    pub ended: String,
+}
+
+impl RunSummary {
+    pub fn new(seed: u64) -> Self {
+        RunSummary {
+            seed,
+            max_heat: 1.0,
+            ..Default::default()
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let scores: Vec<_> = self.scores.iter().map(|s| s.to_string()).collect();
+        let deaths: Vec<_> = self
+            .deaths
+            .iter()
+            .map(|d| {
+                format!(
+                    r#"{{"at":{},"player":{}{}}}"#,
+                    json::float(d.at),
+                    d.player,
+                    d.cause.json_fields()
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"schema":{},"seed":{},"seconds":{},"scores":[{}],"kills":{},"max_heat":{},"continues":{},"deaths":[{}],"ended":{}}}"#,
+            SCHEMA,
+            self.seed,
+            json::float(self.seconds),
+            scores.join(","),
+            self.kills,
+            json::float(self.max_heat),
+            self.continues,
+            deaths.join(","),
+            json::string(&self.ended)
+        )
+    }
+}
+
+/// The file a batch is sent from, summaries queued meanwhile go to a fresh `queue_path`.
+/// The batch stays there until the endpoint takes it, one that never got through goes first.
+pub fn sending_path(queue_path: &str) -> String {
+    format!("{}.sending", queue_path)
+}
+
+/// Queued summaries as one request body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Batch {
+    pub body: String,
+    /// summaries in the body
+    pub runs: usize,
+}
+
+/// The summaries queued in `text`, one JSON line each, as `{"runs": [...]}`.
+/// `None` when there are none.
+pub fn batch(text: &str) -> Option<Batch> {
+    let runs: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    if runs.is_empty() {
+        return None;
+    }
+    Some(Batch {
+        body: format!(r#"{{"runs":[{}]}}"#, runs.join(",")),
+        runs: runs.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queued_lines_make_one_batch() {
+        assert_eq!(batch(""), None);
+        assert_eq!(batch("\n  \n"), None);
+
+        let text = format!(
+            "{}\n\n{}\n",
+            RunSummary::new(1).to_json(),
+            RunSummary::new(2).to_json()
+        );
+        let batch = batch(&text).unwrap();
+        assert_eq!(batch.runs, 2);
+        let body: serde_json::Value = serde_json::from_str(&batch.body).unwrap();
+        assert_eq!(body["runs"][1]["seed"], 2);
+    }
+}